@@ -107,6 +107,10 @@ async def discover_and_register_tools():
                 name = container.name
                 if name in ["mcp-manager", "traefik"]:
                     continue
+                # Skip {app}-green containers during a blue/green rollout
+                app_label = container.labels.get("mcphub.app")
+                if app_label and app_label != name:
+                    continue
 
                 schema = await fetch_openapi_schema(name)
                 if schema and "paths" in schema:
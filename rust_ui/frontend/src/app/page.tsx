@@ -9,12 +9,49 @@ import { ScrollArea } from "@/components/ui/scroll-area";
 import { Input } from "@/components/ui/input";
 import ProjectGroupsDashboard from "@/components/ProjectGroupsDashboard";
 
+interface Resources {
+  memory?: string;
+  cpus?: number;
+  pids?: number;
+}
+
 interface AppStatus {
   name: string;
   status: string;
   auth_type: string;
+  resources?: Resources;
+  crash_loop?: boolean;
+  restart_count?: number;
+  stats?: Stats | null;
+  replicas?: ReplicaStatus[];
+}
+
+interface ReplicaStatus {
+  name: string;
+  status: string;
+  running: boolean;
 }
 
+interface Stats {
+  cpu_percent: number;
+  memory_bytes: number;
+  memory_limit: number;
+  net_rx_rate: number;
+  net_tx_rate: number;
+}
+
+const statsLabel = (s?: Stats | null) =>
+  s ? `CPU ${s.cpu_percent.toFixed(1)}% · ${Math.round(s.memory_bytes / 1048576)} MiB` : "";
+
+const resourceLabel = (r?: Resources) => {
+  if (!r) return "";
+  const parts = [];
+  if (r.memory) parts.push(`${r.memory} mem`);
+  if (r.cpus) parts.push(`${r.cpus} CPU`);
+  if (r.pids) parts.push(`${r.pids} PIDs`);
+  return parts.join(" · ");
+};
+
 interface AuthConfig {
   auth_type: "none" | "api_key" | "entra_id";
   api_key?: string;
@@ -35,6 +72,8 @@ export default function Dashboard() {
   const [createLoading, setCreateLoading] = useState(false);
   const [showCreateDialog, setShowCreateDialog] = useState(false);
   const [newAppName, setNewAppName] = useState("");
+  const [templates, setTemplates] = useState<{ id: string; name: string; language: string; description: string }[]>([]);
+  const [newAppTemplate, setNewAppTemplate] = useState("");
 
   // Auth settings modal
   const [showAuthDialog, setShowAuthDialog] = useState<string | null>(null);
@@ -174,8 +213,14 @@ export default function Dashboard() {
   useEffect(() => {
     if (!isAuthenticated) return;
     fetchApps();
-    const interval = setInterval(fetchApps, 5000);
-    return () => clearInterval(interval);
+    // Status changes are pushed; the interval keeps usage stats fresh
+    const events = new EventSource("/api/events");
+    events.addEventListener("app", () => fetchApps());
+    const interval = setInterval(fetchApps, 10000);
+    return () => {
+      events.close();
+      clearInterval(interval);
+    };
   }, [isAuthenticated]);
 
   const setAppLoading = (appName: string, isLoading: boolean) => {
@@ -190,7 +235,25 @@ export default function Dashboard() {
   const handleDeploy = async (appName: string) => {
     setAppLoading(appName, true);
     try {
-      await fetch(`/api/deploy/${appName}`, { method: "POST" });
+      const res = await fetch(`/api/deploy/${appName}`, { method: "POST" });
+      const data = await res.json();
+      if (data.job_id) {
+        // Deploy runs as a background job; wait until it finishes
+        for (;;) {
+          await new Promise((resolve) => setTimeout(resolve, 2000));
+          const jobRes = await fetch(`/api/jobs/${data.job_id}`);
+          if (!jobRes.ok) break;
+          const job = await jobRes.json();
+          if (job.status !== "queued" && job.status !== "running") {
+            if (job.status === "failed") {
+              // Crashed deploys carry the container's last log lines
+              const crashLogs = job.result?.state === "crashed" ? `\n\n${job.result.logs.join("\n")}` : "";
+              alert(`デプロイに失敗しました: ${job.error}${crashLogs}`);
+            }
+            break;
+          }
+        }
+      }
       await fetchApps();
     } finally {
       setAppLoading(appName, false);
@@ -209,9 +272,10 @@ export default function Dashboard() {
 
   const handleDelete = async (appName: string) => {
     if (!confirm(`「${appName}」を完全に削除しますか？\nコンテナとアプリディレクトリが削除されます。`)) return;
+    const purge = confirm(`データボリュームも削除しますか？\n「キャンセル」でボリュームを残します。`);
     setAppLoading(appName, true);
     try {
-      await fetch(`/api/delete/${appName}`, { method: "POST" });
+      await fetch(`/api/delete/${appName}?volumes=${purge ? "purge" : "keep"}`, { method: "POST" });
       await fetchApps();
       setLogs((prev) => {
         const newLogs = { ...prev };
@@ -380,7 +444,8 @@ export default function Dashboard() {
     if (isCreateDisabled) return;
     setCreateLoading(true);
     try {
-      const res = await fetch(`/api/create/${newAppName.trim()}`, { method: "POST" });
+      const query = newAppTemplate ? `?template=${encodeURIComponent(newAppTemplate)}` : "";
+      const res = await fetch(`/api/create/${newAppName.trim()}${query}`, { method: "POST" });
       if (res.ok) {
         setShowCreateDialog(false);
         setNewAppName("");
@@ -391,6 +456,17 @@ export default function Dashboard() {
     }
   };
 
+  const openCreateDialog = async () => {
+    setNewAppName("");
+    setShowCreateDialog(true);
+    const res = await fetch("/api/templates");
+    if (res.ok) {
+      const data = await res.json();
+      setTemplates(data.templates);
+      setNewAppTemplate(data.default);
+    }
+  };
+
   const copyToClipboard = (text: string) => {
     if (navigator.clipboard && window.isSecureContext) {
       navigator.clipboard.writeText(text);
@@ -802,7 +878,7 @@ export default function Dashboard() {
                   <h2 className="text-2xl font-bold tracking-tight">すべてのコンテナ</h2>
                   <p className="text-muted-foreground mt-1">システム上のすべてのコンテナを管理します</p>
                 </div>
-                <Button onClick={() => { setNewAppName(""); setShowCreateDialog(true); }}>
+                <Button onClick={openCreateDialog}>
                   <Plus className="mr-2 h-4 w-4" />
                   New App
                 </Button>
@@ -818,7 +894,11 @@ export default function Dashboard() {
                   <div className="flex justify-between items-start">
                     <div>
                       <CardTitle className="text-xl">{app.name}</CardTitle>
-                      <CardDescription>Container App</CardDescription>
+                      <CardDescription>
+                        Container App
+                        {resourceLabel(app.resources) && <span className="ml-1">· {resourceLabel(app.resources)}</span>}
+                        {statsLabel(app.stats) && <span className="block text-xs">{statsLabel(app.stats)}</span>}
+                      </CardDescription>
                     </div>
                     <div className="flex gap-1">
                       {authBadge && (
@@ -827,8 +907,13 @@ export default function Dashboard() {
                           {authBadge}
                         </Badge>
                       )}
-                      <Badge variant={isUp ? "default" : "secondary"}>
+                      <Badge
+                        variant={app.crash_loop ? "destructive" : isUp ? "default" : "secondary"}
+                        title={app.replicas?.map((r) => `${r.name}: ${r.status}`).join("\n")}
+                      >
                         {isUp ? "Running" : app.status}
+                        {(app.replicas?.length ?? 0) > 1 &&
+                          ` ×${app.replicas!.filter((r) => r.running).length}/${app.replicas!.length}`}
                       </Badge>
                     </div>
                   </div>
@@ -1018,6 +1103,23 @@ export default function Dashboard() {
                   This app name already exists.
                 </p>
               )}
+              {templates.length > 0 && (
+                <div>
+                  <label className="text-sm font-medium mb-2 block">Template</label>
+                  <select
+                    className="w-full h-9 rounded-md border border-input bg-background px-3 text-sm ring-offset-background focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-ring"
+                    value={newAppTemplate}
+                    onChange={(e) => setNewAppTemplate(e.target.value)}
+                  >
+                    {templates.map((t) => (
+                      <option key={t.id} value={t.id}>{t.name} ({t.language})</option>
+                    ))}
+                  </select>
+                  <p className="text-xs text-muted-foreground mt-1">
+                    {templates.find((t) => t.id === newAppTemplate)?.description}
+                  </p>
+                </div>
+              )}
             </CardContent>
             <CardFooter className="flex justify-end gap-2">
               <Button variant="outline" onClick={() => setShowCreateDialog(false)}>
@@ -42,6 +42,8 @@ interface ContainerSummary {
   running: number;
   stopped: number;
   error: number;
+  cpuPercent?: number;
+  memoryBytes?: number;
 }
 
 interface Group {
@@ -87,6 +89,11 @@ function ContainerStatusBadges({ summary }: { summary: ContainerSummary }) {
           コンテナなし
         </Badge>
       )}
+      {summary.running > 0 && summary.memoryBytes !== undefined && (
+        <Badge variant="outline" className="text-xs">
+          CPU {summary.cpuPercent?.toFixed(1)}% · {Math.round(summary.memoryBytes / 1048576)} MiB
+        </Badge>
+      )}
     </div>
   );
 }
@@ -137,8 +144,13 @@ export default function ProjectGroupsDashboard({ onGroupSelect }: Props) {
 
   useEffect(() => {
     fetchData();
+    const events = new EventSource("/api/events");
+    events.addEventListener("group", () => fetchData());
     const interval = setInterval(fetchData, 10000);
-    return () => clearInterval(interval);
+    return () => {
+      events.close();
+      clearInterval(interval);
+    };
   }, [fetchData]);
 
   const handleCreateGroup = async () => {
@@ -0,0 +1,355 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::locks::LockConflict;
+use crate::manifest::Resources;
+use crate::secrets::StoredVar;
+use crate::traefik::ExtraRoute;
+use crate::{code_server_config_dir, now_iso8601, AppState, AuthAppConfig, Group};
+
+// ── Backup and restore ──
+//
+// GET /api/apps/{app}/backup streams a tar.gz with the app's source
+// (app/), its code-server config, password and extensions (code-server/) and
+// mcphub-backup.json holding the auth_config.json entry, the env vars and
+// secrets (still encrypted), resource limits, routes and the groups the app
+// belongs to. POST /api/apps/restore takes such an archive as the request
+// body and recreates the app, optionally as `?name=`. The archive is unpacked
+// into a staging directory first, so a bad archive or a name clash leaves
+// nothing behind. A restored app is not started; deploy it as usual.
+//
+// Env values only decrypt on a hub with the same secrets key; the others are
+// listed in the response as `missing_env` and have to be set again.
+//
+// The archive helpers here are shared with export/import bundles.
+
+const METADATA_FILE: &str = "mcphub-backup.json";
+const FORMAT_VERSION: u32 = 1;
+const STAGING_PREFIX: &str = "/apps/.restore-";
+// Archives are read whole before unpacking
+const DEFAULT_RESTORE_MAX_BYTES: usize = 1024 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize)]
+struct GroupRef {
+    id: String,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupMetadata {
+    format: u32,
+    app: String,
+    created_at: String,
+    auth: Option<AuthAppConfig>,
+    #[serde(default)]
+    groups: Vec<GroupRef>,
+    #[serde(default)]
+    env: BTreeMap<String, StoredVar>,
+    #[serde(default)]
+    resources: Option<Resources>,
+    #[serde(default)]
+    routes: Vec<ExtraRoute>,
+}
+
+#[derive(Deserialize)]
+pub struct RestoreQuery {
+    // Restore under this name instead of the one in the archive
+    name: Option<String>,
+    #[serde(default)]
+    wait: bool,
+}
+
+pub fn restore_max_bytes() -> usize {
+    std::env::var("RESTORE_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RESTORE_MAX_BYTES)
+}
+
+// Forwards whatever the tar/gzip encoder writes to the response body
+pub struct ChannelWriter(mpsc::Sender<Result<Bytes, std::io::Error>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "client disconnected"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+pub type ArchiveBuilder = tar::Builder<flate2::write::GzEncoder<std::io::BufWriter<ChannelWriter>>>;
+
+// Streams a tar.gz filled in by `fill` on a blocking thread as a download
+pub fn stream_archive<F>(filename: String, fill: F) -> Response
+where
+    F: FnOnce(&mut ArchiveBuilder) -> std::io::Result<()> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel::<Result<Bytes, std::io::Error>>(16);
+    let name = filename.clone();
+    tokio::task::spawn_blocking(move || {
+        // Bigger chunks than the encoder's own writes
+        let out = std::io::BufWriter::with_capacity(64 * 1024, ChannelWriter(tx.clone()));
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(out, flate2::Compression::default()));
+        builder.follow_symlinks(false);
+        let result = fill(&mut builder).and_then(|_| builder.into_inner()?.finish()?.flush());
+        if let Err(e) = result {
+            eprintln!("Writing {} failed: {}", name, e);
+            // Aborts the response so the client doesn't keep a truncated archive
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/gzip".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+pub fn append_json<W: Write>(builder: &mut tar::Builder<W>, path: &str, value: &impl Serialize) -> std::io::Result<()> {
+    let json = serde_json::to_vec_pretty(value).map_err(std::io::Error::other)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs());
+    builder.append_data(&mut header, path, json.as_slice())
+}
+
+// Unpacked upload under /apps, removed when dropped
+pub struct Staging(PathBuf);
+
+impl Staging {
+    pub fn path(&self) -> &FsPath {
+        &self.0
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+// Unpacks an uploaded tar.gz into a new staging directory and returns the
+// parsed `metadata_file`. Only that file and entries under `dirs` are accepted,
+// and `app/` must be present.
+pub async fn unpack_upload<M: DeserializeOwned + Send + 'static>(
+    body: Bytes,
+    metadata_file: &'static str,
+    dirs: &'static [&'static str],
+) -> Result<(M, Staging), String> {
+    let staging = Staging(PathBuf::from(format!("{}{:016x}", STAGING_PREFIX, rand::random::<u64>())));
+    let dir = staging.0.clone();
+    let metadata = tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create staging directory: {}", e))?;
+        unpack_archive(&body, &dir, metadata_file, dirs)
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()))?;
+    Ok((metadata, staging))
+}
+
+fn unpack_archive<M: DeserializeOwned>(data: &[u8], staging: &FsPath, metadata_file: &str, dirs: &[&str]) -> Result<M, String> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(data));
+    let mut metadata = None;
+    let entries = archive.entries().map_err(|e| format!("Not a tar.gz archive: {}", e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Corrupt archive: {}", e))?;
+        let path = entry.path().map_err(|e| format!("Corrupt archive: {}", e))?.into_owned();
+        let top = path.components().next().map(|c| c.as_os_str().to_string_lossy().to_string()).unwrap_or_default();
+        if top == metadata_file {
+            let mut json = Vec::new();
+            std::io::Read::read_to_end(&mut entry, &mut json).map_err(|e| format!("Corrupt archive: {}", e))?;
+            metadata = Some(serde_json::from_slice::<M>(&json).map_err(|e| format!("Invalid {}: {}", metadata_file, e))?);
+        } else if dirs.contains(&top.as_str()) {
+            // unpack_in refuses paths that would land outside `staging`
+            let unpacked = entry.unpack_in(staging).map_err(|e| format!("Failed to unpack {}: {}", path.display(), e))?;
+            if !unpacked {
+                return Err(format!("Unsafe path in archive: {}", path.display()));
+            }
+        } else {
+            return Err(format!("Unexpected entry in archive: {}", path.display()));
+        }
+    }
+    let metadata = metadata.ok_or_else(|| format!("Archive has no {}", metadata_file))?;
+    if !staging.join("app").is_dir() {
+        return Err("Archive has no app directory".to_string());
+    }
+    Ok(metadata)
+}
+
+// Adds the app to a group's members
+pub fn join_group(group: &mut Group, app_name: &str) {
+    if !group.containers.iter().any(|c| c == app_name) {
+        group.containers.push(app_name.to_string());
+        group.updated_at = now_iso8601();
+    }
+}
+
+fn error(status: StatusCode, msg: String) -> Response {
+    (status, Json(serde_json::json!({"error": msg}))).into_response()
+}
+
+// ── Handlers ──
+
+pub async fn backup_app(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> Response {
+    if !crate::valid_app_name(&app_name) || !FsPath::new(&format!("/apps/{}", app_name)).is_dir() {
+        return error(StatusCode::NOT_FOUND, "App not found".to_string());
+    }
+    let metadata = BackupMetadata {
+        format: FORMAT_VERSION,
+        app: app_name.clone(),
+        created_at: now_iso8601(),
+        auth: state.auth_config.read().unwrap().get(&app_name).cloned(),
+        groups: state.groups.read().unwrap()
+            .iter()
+            .filter(|g| g.containers.contains(&app_name))
+            .map(|g| GroupRef { id: g.id.clone(), name: g.name.clone() })
+            .collect(),
+        env: state.secrets.sealed(&app_name),
+        resources: state.resources_config.read().unwrap().get(&app_name).cloned(),
+        routes: state.routes_config.read().unwrap().get(&app_name).cloned().unwrap_or_default(),
+    };
+
+    let filename = format!("{}-{}.tar.gz", app_name, metadata.created_at.replace(':', ""));
+    stream_archive(filename, move |builder| {
+        append_json(builder, METADATA_FILE, &metadata)?;
+        builder.append_dir_all("app", format!("/apps/{}", app_name))?;
+        let cs_dir = code_server_config_dir(&app_name);
+        if FsPath::new(&cs_dir).is_dir() {
+            builder.append_dir_all("code-server", &cs_dir)?;
+        }
+        Ok(())
+    })
+}
+
+pub async fn restore_app(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RestoreQuery>,
+    body: Bytes,
+) -> Result<Response, LockConflict> {
+    let (metadata, staging) = match unpack_upload::<BackupMetadata>(body, METADATA_FILE, &["app", "code-server"]).await {
+        Ok(unpacked) => unpacked,
+        Err(e) => return Ok(error(StatusCode::BAD_REQUEST, e)),
+    };
+    if metadata.format > FORMAT_VERSION {
+        return Ok(error(StatusCode::BAD_REQUEST, format!("Backup format {} is newer than this hub supports", metadata.format)));
+    }
+    let app_name = query.name.unwrap_or_else(|| metadata.app.clone());
+    install(&state, &app_name, &metadata, staging.path(), query.wait).await
+}
+
+// Moves the staged directories into place and merges auth, env, resources,
+// routes and group entries
+async fn install(
+    state: &AppState,
+    app_name: &str,
+    metadata: &BackupMetadata,
+    staging: &FsPath,
+    wait: bool,
+) -> Result<Response, LockConflict> {
+    if !crate::valid_app_name(app_name) {
+        return Ok(error(StatusCode::BAD_REQUEST, format!("Invalid app name '{}'", app_name)));
+    }
+    let _guard = state.locks.lock(app_name, "restore", wait).await?;
+    let app_dir = format!("/apps/{}", app_name);
+    let cs_dir = code_server_config_dir(app_name);
+    if FsPath::new(&app_dir).exists() {
+        return Ok(error(StatusCode::CONFLICT, format!("App '{}' already exists", app_name)));
+    }
+
+    if let Err(e) = std::fs::rename(staging.join("app"), &app_dir) {
+        return Ok(error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to restore app directory: {}", e)));
+    }
+    let _ = std::fs::remove_dir_all(&cs_dir);
+    if staging.join("code-server").is_dir() {
+        let _ = std::fs::create_dir_all(crate::CODE_SERVER_HOST_ROOT);
+        if let Err(e) = std::fs::rename(staging.join("code-server"), &cs_dir) {
+            eprintln!("Failed to restore code-server config for {}: {}", app_name, e);
+        }
+    }
+    crate::ensure_code_server_host_dirs(app_name);
+
+    if let Some(auth) = &metadata.auth {
+        let mut config = state.auth_config.write().unwrap();
+        config.insert(app_name.to_string(), auth.clone());
+        let _ = crate::save_auth_config(&config);
+    }
+    let missing_env = match state.secrets.restore_sealed(app_name, &metadata.app, &metadata.env) {
+        Ok(unreadable) => unreadable,
+        Err(e) => {
+            eprintln!("Failed to restore env vars for {}: {}", app_name, e);
+            metadata.env.keys().cloned().collect()
+        }
+    };
+    let env: Vec<&String> = metadata.env.keys().filter(|name| !missing_env.contains(name)).collect();
+    if let Some(resources) = &metadata.resources {
+        let mut config = state.resources_config.write().unwrap();
+        config.insert(app_name.to_string(), resources.clone());
+        let _ = crate::save_resources_config(&config);
+    }
+    if !metadata.routes.is_empty() {
+        let mut config = state.routes_config.write().unwrap();
+        config.insert(app_name.to_string(), metadata.routes.clone());
+        let _ = crate::save_routes_config(&config);
+    }
+
+    // Groups are matched by ID, then by name; missing ones are reported
+    let mut joined = Vec::new();
+    let mut missing = Vec::new();
+    {
+        let mut groups = state.groups.write().unwrap();
+        for wanted in &metadata.groups {
+            let found = groups.iter().position(|g| g.id == wanted.id)
+                .or_else(|| groups.iter().position(|g| g.name == wanted.name));
+            match found {
+                Some(i) => {
+                    join_group(&mut groups[i], app_name);
+                    joined.push(groups[i].name.clone());
+                }
+                None => missing.push(wanted.name.clone()),
+            }
+        }
+        if !joined.is_empty() {
+            let _ = crate::save_groups(&groups);
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "app": app_name,
+        "restored_from": metadata.app,
+        "backup_created_at": metadata.created_at,
+        "env": env,
+        "missing_env": missing_env,
+        "groups": joined,
+        "missing_groups": missing,
+    }))
+    .into_response())
+}
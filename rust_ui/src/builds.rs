@@ -0,0 +1,379 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{sse::{Event, Sse}, IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io::Write;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::runtime::LogSink;
+use crate::{now_iso8601, AppState};
+
+// ── Build history ──
+//
+// Every deploy/rebuild is recorded under a build ID. Metadata for all apps
+// lives in /apps/builds.json; each build's full log is appended line by line
+// to /apps/.builds/{app}/{id}.log while it runs. Running builds also keep
+// their log in memory so followers can replay it and then tail new lines.
+
+const BUILDS_PATH: &str = "/apps/builds.json";
+const BUILD_LOGS_DIR: &str = "/apps/.builds";
+const DEFAULT_MAX_BUILDS: usize = 20;
+const FOLLOW_BUFFER: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    // Deploy button in the management UI
+    Deploy,
+    // Rebuild from the app dashboard
+    Rebuild,
+    // Direct API call (scripts, curl)
+    Api,
+    // Git pull with ?rebuild=true
+    Pull,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    // The hub restarted while the build was running
+    Interrupted,
+}
+
+impl BuildStatus {
+    fn label(self) -> &'static str {
+        match self {
+            BuildStatus::Running => "running",
+            BuildStatus::Succeeded => "succeeded",
+            BuildStatus::Failed => "failed",
+            BuildStatus::Cancelled => "cancelled",
+            BuildStatus::Interrupted => "interrupted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Build {
+    pub id: String,
+    pub app: String,
+    pub trigger: Trigger,
+    pub status: BuildStatus,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    // ID of the built image, once the build step succeeded
+    pub image_digest: Option<String>,
+    // HEAD of the source checkout for apps created from Git
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub error: Option<String>,
+}
+
+struct LiveLog {
+    lines: Vec<String>,
+    tx: broadcast::Sender<String>,
+}
+
+#[derive(Clone)]
+pub struct BuildStore {
+    // Oldest first per app
+    apps: Arc<RwLock<HashMap<String, Vec<Build>>>>,
+    live: Arc<RwLock<HashMap<String, LiveLog>>>,
+    max_builds: usize,
+}
+
+fn save_builds(apps: &HashMap<String, Vec<Build>>) -> Result<(), std::io::Error> {
+    let json = serde_json::to_string_pretty(apps).map_err(std::io::Error::other)?;
+    std::fs::write(BUILDS_PATH, json)
+}
+
+fn log_path(app_name: &str, id: &str) -> String {
+    format!("{}/{}/{}.log", BUILD_LOGS_DIR, app_name, id)
+}
+
+impl BuildStore {
+    pub fn from_env() -> Self {
+        let mut apps: HashMap<String, Vec<Build>> = std::fs::read_to_string(BUILDS_PATH)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        let mut interrupted = false;
+        for build in apps.values_mut().flatten().filter(|b| b.status == BuildStatus::Running) {
+            build.status = BuildStatus::Interrupted;
+            interrupted = true;
+        }
+        if interrupted {
+            let _ = save_builds(&apps);
+        }
+        let max_builds = std::env::var("MAX_BUILD_HISTORY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n >= 1)
+            .unwrap_or(DEFAULT_MAX_BUILDS);
+        BuildStore {
+            apps: Arc::new(RwLock::new(apps)),
+            live: Arc::new(RwLock::new(HashMap::new())),
+            max_builds,
+        }
+    }
+
+    // Newest first
+    pub fn list(&self, app_name: &str) -> Vec<Build> {
+        let apps = self.apps.read().unwrap();
+        let mut list = apps.get(app_name).cloned().unwrap_or_default();
+        list.reverse();
+        list
+    }
+
+    pub fn get(&self, app_name: &str, id: &str) -> Option<Build> {
+        let apps = self.apps.read().unwrap();
+        apps.get(app_name)?.iter().find(|b| b.id == id).cloned()
+    }
+
+    fn update(&self, app_name: &str, id: &str, f: impl FnOnce(&mut Build)) {
+        let mut apps = self.apps.write().unwrap();
+        if let Some(build) = apps.get_mut(app_name).and_then(|b| b.iter_mut().find(|b| b.id == id)) {
+            f(build);
+            let _ = save_builds(&apps);
+        }
+    }
+
+    // Registers a running build; lines sent through the recorder's sinks make up its log
+    pub fn start(&self, app_name: &str, trigger: Trigger) -> BuildRecorder {
+        let id = format!("{:016x}", rand::random::<u64>());
+        let build = Build {
+            id: id.clone(),
+            app: app_name.to_string(),
+            trigger,
+            status: BuildStatus::Running,
+            started_at: now_iso8601(),
+            finished_at: None,
+            image_digest: None,
+            commit: crate::git::head_commit(app_name),
+            error: None,
+        };
+        let pruned: Vec<Build> = {
+            let mut apps = self.apps.write().unwrap();
+            let builds = apps.entry(app_name.to_string()).or_default();
+            builds.push(build);
+            let excess = builds.len().saturating_sub(self.max_builds);
+            let pruned = builds.drain(..excess).collect();
+            let _ = save_builds(&apps);
+            pruned
+        };
+        for old in pruned {
+            let _ = std::fs::remove_file(log_path(app_name, &old.id));
+        }
+
+        let _ = std::fs::create_dir_all(format!("{}/{}", BUILD_LOGS_DIR, app_name));
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path(app_name, &id))
+            .map_err(|e| eprintln!("Failed to open build log for {}: {}", app_name, e))
+            .ok();
+        let (tx, _) = broadcast::channel(FOLLOW_BUFFER);
+        self.live.write().unwrap().insert(id.clone(), LiveLog { lines: Vec::new(), tx });
+
+        let (record, mut lines) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let outcome: Arc<Mutex<Option<Result<(), String>>>> = Arc::new(Mutex::new(None));
+        let store = self.clone();
+        let writer_app = app_name.to_string();
+        let writer_id = id.clone();
+        let writer_outcome = outcome.clone();
+        let writer = tokio::spawn(async move {
+            while let Some(line) = lines.recv().await {
+                if let Some(f) = file.as_mut() {
+                    let _ = writeln!(f, "{}", line);
+                }
+                let mut live = store.live.write().unwrap();
+                if let Some(entry) = live.get_mut(&writer_id) {
+                    let _ = entry.tx.send(line.clone());
+                    entry.lines.push(line);
+                }
+            }
+
+            // All sinks are gone: either finish() was called or the job was aborted
+            let outcome = writer_outcome.lock().unwrap().take();
+            store.update(&writer_app, &writer_id, |build| {
+                build.finished_at = Some(now_iso8601());
+                match outcome {
+                    Some(Ok(())) => build.status = BuildStatus::Succeeded,
+                    Some(Err(e)) => {
+                        build.status = BuildStatus::Failed;
+                        build.error = Some(e);
+                    }
+                    None => build.status = BuildStatus::Cancelled,
+                }
+            });
+            // Dropping the sender ends followers after the final status is saved
+            store.live.write().unwrap().remove(&writer_id);
+        });
+
+        BuildRecorder {
+            store: self.clone(),
+            app_name: app_name.to_string(),
+            id,
+            record,
+            outcome,
+            writer,
+        }
+    }
+
+    pub fn remove_app(&self, app_name: &str) {
+        let mut apps = self.apps.write().unwrap();
+        if apps.remove(app_name).is_some() {
+            let _ = save_builds(&apps);
+        }
+        let _ = std::fs::remove_dir_all(format!("{}/{}", BUILD_LOGS_DIR, app_name));
+    }
+
+    // Moves the history and logs of finished builds to a renamed app
+    pub fn rename_app(&self, from: &str, to: &str) {
+        let mut apps = self.apps.write().unwrap();
+        if let Some(mut builds) = apps.remove(from) {
+            for build in &mut builds {
+                build.app = to.to_string();
+            }
+            apps.insert(to.to_string(), builds);
+            let _ = save_builds(&apps);
+        }
+        let _ = std::fs::rename(format!("{}/{}", BUILD_LOGS_DIR, from), format!("{}/{}", BUILD_LOGS_DIR, to));
+    }
+}
+
+pub struct BuildRecorder {
+    store: BuildStore,
+    app_name: String,
+    id: String,
+    record: LogSink,
+    outcome: Arc<Mutex<Option<Result<(), String>>>>,
+    writer: JoinHandle<()>,
+}
+
+impl BuildRecorder {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    // Returns a sink whose lines go to the build log and on to `out`
+    pub fn tee(&self, out: LogSink) -> LogSink {
+        let record = self.record.clone();
+        let (sink, mut lines) = tokio::sync::mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            while let Some(line) = lines.recv().await {
+                let _ = record.send(line.clone());
+                let _ = out.send(line);
+            }
+        });
+        sink
+    }
+
+    pub fn set_image(&self, image_id: &str) {
+        self.store.update(&self.app_name, &self.id, |build| build.image_digest = Some(image_id.to_string()));
+    }
+
+    // Waits for every sink from tee() to be dropped, then stores the final status
+    pub async fn finish(self, outcome: Result<(), String>) {
+        *self.outcome.lock().unwrap() = Some(outcome);
+        drop(self.record);
+        let _ = self.writer.await;
+    }
+}
+
+// Browsers tag their fetches with Sec-Fetch-*; anything else is a script hitting the API
+pub fn trigger_from_headers(headers: &axum::http::HeaderMap, from_ui: Trigger) -> Trigger {
+    if headers.contains_key("sec-fetch-mode") {
+        from_ui
+    } else {
+        Trigger::Api
+    }
+}
+
+// ── Handlers ──
+
+fn not_found() -> Response {
+    (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Build not found"}))).into_response()
+}
+
+pub async fn list_builds(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "app": app_name, "builds": state.builds.list(&app_name) }))
+}
+
+pub async fn get_build(
+    State(state): State<Arc<AppState>>,
+    Path((app_name, id)): Path<(String, String)>,
+) -> Response {
+    let Some(build) = state.builds.get(&app_name, &id) else {
+        return not_found();
+    };
+    let log: Vec<String> = std::fs::read_to_string(log_path(&app_name, &id))
+        .map(|s| s.lines().map(|l| l.to_string()).collect())
+        .unwrap_or_default();
+    let mut value = serde_json::to_value(&build).unwrap_or_default();
+    value["log"] = serde_json::json!(log);
+    Json(value).into_response()
+}
+
+// Replays the stored log as SSE data events; while the build is still running
+// new lines follow as they arrive. Ends with a "done" event carrying the status.
+pub async fn stream_build_log(
+    State(state): State<Arc<AppState>>,
+    Path((app_name, id)): Path<(String, String)>,
+) -> Response {
+    if state.builds.get(&app_name, &id).is_none() {
+        return not_found();
+    }
+    // Snapshot and subscribe under one lock so no line is missed or repeated
+    let (backlog, follow) = match state.builds.live.read().unwrap().get(&id) {
+        Some(live) => (live.lines.clone(), Some(live.tx.subscribe())),
+        None => (
+            std::fs::read_to_string(log_path(&app_name, &id))
+                .map(|s| s.lines().map(|l| l.to_string()).collect())
+                .unwrap_or_default(),
+            None,
+        ),
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(256);
+    tokio::spawn(async move {
+        for line in backlog {
+            if tx.send(Ok(Event::default().data(line))).await.is_err() {
+                return;
+            }
+        }
+        if let Some(mut follow) = follow {
+            loop {
+                match follow.recv().await {
+                    Ok(line) => {
+                        if tx.send(Ok(Event::default().data(line))).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+        let status = state.builds.get(&app_name, &id).map(|b| b.status).unwrap_or(BuildStatus::Interrupted);
+        let _ = tx.send(Ok(Event::default().event("done").data(status.label()))).await;
+    });
+
+    Sse::new(ReceiverStream::new(rx))
+        .keep_alive(axum::response::sse::KeepAlive::default())
+        .into_response()
+}
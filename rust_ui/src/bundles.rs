@@ -0,0 +1,313 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path as FsPath;
+use std::sync::Arc;
+
+use crate::backup::{append_json, join_group, stream_archive, unpack_upload};
+use crate::locks::LockConflict;
+use crate::manifest;
+use crate::{now_iso8601, AppState, AuthAppConfig, Group};
+
+// ── Export / import between hubs ──
+//
+// GET /api/apps/{app}/export produces a portable bundle: the source tree
+// (mcphub.toml included) and mcphub-bundle.json with the auth settings and
+// the names of the groups the app is in. `?redact=true` leaves the API key
+// out and the importing hub generates a new one. Unlike a backup, hub-local
+// IDE state (password, extensions) stays behind.
+//
+// POST /api/apps/import works out everything the import would do before
+// touching anything: `?dry_run=true` returns just that report, and a
+// blocking conflict returns it with 409. An existing app of the same name is
+// a conflict unless `on_conflict=rename` (next free {app}-importedN) or
+// `on_conflict=replace` (swap in the new source; redeploy to apply). Replace
+// is refused while the app runs, since its container still mounts the tree
+// being swapped out. Groups are joined by name and created when missing.
+
+const BUNDLE_FILE: &str = "mcphub-bundle.json";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct BundleMetadata {
+    format: u32,
+    app: String,
+    exported_at: String,
+    auth: Option<AuthAppConfig>,
+    // The API key was left out of `auth`
+    #[serde(default)]
+    auth_redacted: bool,
+    #[serde(default)]
+    groups: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    redact: bool,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum OnConflict {
+    #[default]
+    Fail,
+    Rename,
+    Replace,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    // Import under this name instead of the one in the bundle
+    name: Option<String>,
+    #[serde(default)]
+    on_conflict: OnConflict,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    wait: bool,
+}
+
+#[derive(Serialize, Default)]
+struct ImportReport {
+    app: String,
+    source_app: String,
+    // Block the import; nothing is written while there are any
+    conflicts: Vec<String>,
+    actions: Vec<String>,
+    warnings: Vec<String>,
+}
+
+fn error(status: StatusCode, msg: String) -> Response {
+    (status, Json(serde_json::json!({"error": msg}))).into_response()
+}
+
+fn app_exists(app_name: &str) -> bool {
+    FsPath::new(&format!("/apps/{}", app_name)).exists()
+}
+
+fn renamed(app_name: &str) -> String {
+    (1..)
+        .map(|i| if i == 1 { format!("{}-imported", app_name) } else { format!("{}-imported{}", app_name, i) })
+        .find(|name| !app_exists(name))
+        .unwrap_or_default()
+}
+
+// Everything the import would do, checked against the hub as it is now
+fn plan(state: &AppState, metadata: &BundleMetadata, staging: &FsPath, query: &ImportQuery) -> ImportReport {
+    let mut app_name = query.name.clone().unwrap_or_else(|| metadata.app.clone());
+    let mut report = ImportReport { source_app: metadata.app.clone(), ..Default::default() };
+    let mut replacing = false;
+
+    if !crate::valid_app_name(&app_name) {
+        report.conflicts.push(format!("'{}' is not a valid app name", app_name));
+    } else if app_exists(&app_name) {
+        match query.on_conflict {
+            OnConflict::Fail => report.conflicts.push(format!(
+                "App '{}' already exists (use on_conflict=rename or on_conflict=replace)",
+                app_name
+            )),
+            OnConflict::Rename => {
+                let new_name = renamed(&app_name);
+                // The suffix can push a long name past the length limit
+                if crate::valid_app_name(&new_name) {
+                    report.warnings.push(format!("App '{}' already exists; importing as '{}'", app_name, new_name));
+                } else {
+                    report.conflicts.push(format!(
+                        "App '{}' already exists and '{}' is not a valid app name; pass ?name= instead",
+                        app_name, new_name
+                    ));
+                }
+                app_name = new_name;
+            }
+            OnConflict::Replace => {
+                replacing = true;
+                if state.status.is_running(&app_name) {
+                    report.conflicts.push(format!("App '{}' is running; stop it before replacing its source", app_name));
+                } else {
+                    report.actions.push(format!("Replace the source of existing app '{}' (redeploy to apply)", app_name));
+                }
+            }
+        }
+    }
+    if !replacing {
+        report.actions.push(format!("Create /apps/{}", app_name));
+    }
+    report.app = app_name;
+
+    if metadata.format > FORMAT_VERSION {
+        report.conflicts.push(format!("Bundle format {} is newer than this hub supports", metadata.format));
+    }
+
+    match std::fs::read_to_string(staging.join("app").join(manifest::MANIFEST_FILE)) {
+        Ok(src) => {
+            if let Err(errors) = manifest::parse(&src) {
+                report.warnings.extend(errors.iter().map(|e| format!("{} (deploys will fail until fixed)", e)));
+            }
+        }
+        Err(_) => report.warnings.push(format!("No {}; the app deploys with defaults", manifest::MANIFEST_FILE)),
+    }
+
+    match &metadata.auth {
+        Some(AuthAppConfig::ApiKey { .. }) if metadata.auth_redacted => {
+            report.actions.push("Set auth api_key with a newly generated key".to_string());
+        }
+        Some(auth) => report.actions.push(format!("Set auth {}", auth_type(auth))),
+        None => {}
+    }
+
+    let groups = state.groups.read().unwrap();
+    for name in &metadata.groups {
+        if groups.iter().any(|g| &g.name == name) {
+            report.actions.push(format!("Join group '{}'", name));
+        } else {
+            report.actions.push(format!("Create group '{}' and join it", name));
+        }
+    }
+    report
+}
+
+fn auth_type(auth: &AuthAppConfig) -> &'static str {
+    match auth {
+        AuthAppConfig::None => "none",
+        AuthAppConfig::ApiKey { .. } => "api_key",
+        AuthAppConfig::EntraId { .. } => "entra_id",
+    }
+}
+
+// Puts the staged source in place; a replaced app's old tree is removed last
+fn install_source(staging: &FsPath, app_name: &str) -> Result<(), String> {
+    let app_dir = format!("/apps/{}", app_name);
+    let previous = staging.join("previous");
+    let replacing = FsPath::new(&app_dir).exists();
+    if replacing {
+        std::fs::rename(&app_dir, &previous).map_err(|e| format!("Failed to move the existing app aside: {}", e))?;
+    }
+    if let Err(e) = std::fs::rename(staging.join("app"), &app_dir) {
+        if replacing {
+            let _ = std::fs::rename(&previous, &app_dir);
+        }
+        return Err(format!("Failed to write app directory: {}", e));
+    }
+    Ok(())
+}
+
+// ── Handlers ──
+
+pub async fn export_app(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    if !crate::valid_app_name(&app_name) || !app_exists(&app_name) {
+        return error(StatusCode::NOT_FOUND, "App not found".to_string());
+    }
+    let mut auth = state.auth_config.read().unwrap().get(&app_name).cloned();
+    let auth_redacted = query.redact && matches!(auth, Some(AuthAppConfig::ApiKey { .. }));
+    if auth_redacted {
+        auth = Some(AuthAppConfig::ApiKey { api_key: String::new() });
+    }
+    let metadata = BundleMetadata {
+        format: FORMAT_VERSION,
+        app: app_name.clone(),
+        exported_at: now_iso8601(),
+        auth,
+        auth_redacted,
+        groups: state.groups.read().unwrap()
+            .iter()
+            .filter(|g| g.containers.contains(&app_name))
+            .map(|g| g.name.clone())
+            .collect(),
+    };
+    stream_archive(format!("{}.mcphub.tar.gz", app_name), move |builder| {
+        append_json(builder, BUNDLE_FILE, &metadata)?;
+        builder.append_dir_all("app", format!("/apps/{}", app_name))
+    })
+}
+
+pub async fn import_app(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<Response, LockConflict> {
+    let (metadata, staging) = match unpack_upload::<BundleMetadata>(body, BUNDLE_FILE, &["app"]).await {
+        Ok(unpacked) => unpacked,
+        Err(e) => return Ok(error(StatusCode::BAD_REQUEST, e)),
+    };
+
+    let report = plan(&state, &metadata, staging.path(), &query);
+    if query.dry_run {
+        return Ok(Json(serde_json::json!({ "dry_run": true, "report": report })).into_response());
+    }
+    if !report.conflicts.is_empty() {
+        return Ok((StatusCode::CONFLICT, Json(serde_json::json!({
+            "error": "Import has conflicts",
+            "report": report,
+        })))
+        .into_response());
+    }
+
+    let app_name = report.app.clone();
+    let _guard = state.locks.lock(&app_name, "import", query.wait).await?;
+    // Another request may have created the app while this one waited
+    if app_exists(&app_name) && query.on_conflict != OnConflict::Replace {
+        return Ok(error(StatusCode::CONFLICT, format!("App '{}' already exists", app_name)));
+    }
+    if app_exists(&app_name) && state.status.is_running(&app_name) {
+        return Ok(error(StatusCode::CONFLICT, format!("App '{}' is running; stop it before replacing its source", app_name)));
+    }
+    if let Err(e) = install_source(staging.path(), &app_name) {
+        return Ok(error(StatusCode::INTERNAL_SERVER_ERROR, e));
+    }
+    crate::ensure_code_server_host_dirs(&app_name);
+
+    let mut api_key = None;
+    if let Some(auth) = &metadata.auth {
+        let auth = match auth {
+            AuthAppConfig::ApiKey { .. } if metadata.auth_redacted => {
+                let key = format!("{:032x}", rand::random::<u128>());
+                api_key = Some(key.clone());
+                AuthAppConfig::ApiKey { api_key: key }
+            }
+            other => other.clone(),
+        };
+        let mut config = state.auth_config.write().unwrap();
+        config.insert(app_name.clone(), auth);
+        let _ = crate::save_auth_config(&config);
+    }
+
+    if !metadata.groups.is_empty() {
+        let mut groups = state.groups.write().unwrap();
+        for name in &metadata.groups {
+            let index = match groups.iter().position(|g| &g.name == name) {
+                Some(i) => i,
+                None => {
+                    groups.push(Group {
+                        id: format!("{:016x}", rand::random::<u64>()),
+                        name: name.clone(),
+                        description: format!("Imported with {}", app_name),
+                        containers: vec![],
+                        resources: Default::default(),
+                        created_at: now_iso8601(),
+                        updated_at: now_iso8601(),
+                    });
+                    groups.len() - 1
+                }
+            };
+            join_group(&mut groups[index], &app_name);
+        }
+        let _ = crate::save_groups(&groups);
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "report": report,
+        "api_key": api_key,
+    }))
+    .into_response())
+}
@@ -0,0 +1,133 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::backup::join_group;
+use crate::locks::{LockConflict, WaitQuery};
+use crate::{code_server_config_dir, code_server_extensions_host_dir, AppState, AuthAppConfig};
+
+// ── Clone ──
+//
+// POST /api/apps/{app}/clone copies the app's source tree to a new app.
+// Auth config (with a freshly generated API key), group membership and the
+// installed IDE extensions are copied on request. The clone always gets its
+// own code-server password and is left stopped; data volumes are not copied,
+// so declared ones start out empty under the new name.
+
+#[derive(Deserialize)]
+pub struct CloneRequest {
+    name: String,
+    #[serde(default)]
+    copy_auth: bool,
+    #[serde(default)]
+    copy_groups: bool,
+    #[serde(default)]
+    copy_extensions: bool,
+}
+
+fn error(status: StatusCode, msg: String) -> Response {
+    (status, Json(serde_json::json!({"error": msg}))).into_response()
+}
+
+// `cp -a` keeps modes and symlinks, and copes with files the container created as root
+async fn copy_tree(from: &str, to: &str) -> Result<(), String> {
+    let output = tokio::process::Command::new("cp")
+        .args(["-a", from, to])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run cp: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+pub async fn clone_app(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Query(query): Query<WaitQuery>,
+    Json(req): Json<CloneRequest>,
+) -> Result<Response, LockConflict> {
+    let source_dir = format!("/apps/{}", app_name);
+    if !crate::valid_app_name(&app_name) || !std::path::Path::new(&source_dir).is_dir() {
+        return Ok(error(StatusCode::NOT_FOUND, "App not found".to_string()));
+    }
+    if !crate::valid_app_name(&req.name) {
+        return Ok(error(StatusCode::BAD_REQUEST, format!("Invalid app name '{}'", req.name)));
+    }
+    let _guard = state.locks.lock(&req.name, "clone", query.wait).await?;
+    let target_dir = format!("/apps/{}", req.name);
+    if std::path::Path::new(&target_dir).exists() {
+        return Ok(error(StatusCode::CONFLICT, format!("App '{}' already exists", req.name)));
+    }
+
+    if let Err(e) = copy_tree(&source_dir, &target_dir).await {
+        let _ = tokio::fs::remove_dir_all(&target_dir).await;
+        return Ok(error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to copy source: {}", e)));
+    }
+
+    let _ = std::fs::remove_dir_all(code_server_config_dir(&req.name));
+    crate::ensure_code_server_host_dirs(&req.name);
+    if req.copy_extensions {
+        let extensions = code_server_extensions_host_dir(&app_name);
+        if std::path::Path::new(&extensions).is_dir() {
+            let target = code_server_extensions_host_dir(&req.name);
+            let _ = std::fs::remove_dir(&target);
+            if let Err(e) = copy_tree(&extensions, &target).await {
+                eprintln!("Failed to copy IDE extensions from {} to {}: {}", app_name, req.name, e);
+                crate::ensure_code_server_host_dirs(&req.name);
+            }
+        }
+    }
+
+    // code-server reads this on start; the dashboard shows it like an auto-generated one
+    let password = format!("{:016x}", rand::random::<u64>());
+    let config_path = format!("{}/config.yaml", code_server_config_dir(&req.name));
+    if let Err(e) = std::fs::write(&config_path, format!("auth: password\npassword: {}\ncert: false\n", password)) {
+        return Ok(error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to write code-server config: {}", e)));
+    }
+
+    let mut api_key = None;
+    if req.copy_auth {
+        let mut config = state.auth_config.write().unwrap();
+        if let Some(auth) = config.get(&app_name).cloned() {
+            let auth = match auth {
+                AuthAppConfig::ApiKey { .. } => {
+                    let key = format!("{:032x}", rand::random::<u128>());
+                    api_key = Some(key.clone());
+                    AuthAppConfig::ApiKey { api_key: key }
+                }
+                other => other,
+            };
+            config.insert(req.name.clone(), auth);
+            let _ = crate::save_auth_config(&config);
+        }
+    }
+
+    let mut joined = Vec::new();
+    if req.copy_groups {
+        let mut groups = state.groups.write().unwrap();
+        for group in groups.iter_mut().filter(|g| g.containers.contains(&app_name)) {
+            join_group(group, &req.name);
+            joined.push(group.name.clone());
+        }
+        if !joined.is_empty() {
+            let _ = crate::save_groups(&groups);
+        }
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "app": req.name,
+        "password": password,
+        "api_key": api_key,
+        "groups": joined,
+    }))
+    .into_response())
+}
@@ -0,0 +1,333 @@
+use axum::{
+    extract::State,
+    response::{sse::{Event, Sse}, IntoResponse, Response},
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::logs::parse_rfc3339;
+use crate::replicas;
+use crate::runtime::{ContainerEvent, ContainerRuntime, RuntimeError};
+use crate::AppState;
+
+// ── Container status cache ──
+//
+// A background watcher follows `docker events` for containers and keeps the
+// state of every container in memory, so listing apps and groups no longer
+// asks Docker on each request. The cache is rebuilt from a full listing when
+// the watcher (re)connects. Each change is broadcast to GET /api/events
+// subscribers as SSE.
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone)]
+struct CachedContainer {
+    // App this container is a replica of
+    app: Option<String>,
+    state: String,
+    running: bool,
+    exit_code: i64,
+    // Unix seconds
+    started_at: Option<i64>,
+    health: Option<String>,
+}
+
+impl CachedContainer {
+    // Same wording as the STATUS column of `docker ps`
+    fn status_text(&self, now: i64) -> String {
+        let up = format!("Up {}", human_duration(now - self.started_at.unwrap_or(now)));
+        match (self.state.as_str(), self.health.as_deref()) {
+            ("paused", _) => format!("{} (Paused)", up),
+            (_, Some("starting")) => format!("{} (health: starting)", up),
+            (_, Some(health)) => format!("{} ({})", up, health),
+            _ => up,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplicaState {
+    pub name: String,
+    pub running: bool,
+    // e.g. "Up 5 minutes (healthy)" or "Exited (1)"
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusChange {
+    pub name: String,
+    // App the container belongs to, if it is one of its replicas
+    pub app: Option<String>,
+    // Docker event action, e.g. "start", "die", "oom", "health_status"
+    pub action: String,
+    pub running: bool,
+    pub state: String,
+    pub exit_code: i64,
+    pub health: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct StatusCache {
+    containers: Arc<RwLock<HashMap<String, CachedContainer>>>,
+    changes: broadcast::Sender<StatusChange>,
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+// Port of Docker's units.HumanDuration
+fn human_duration(secs: i64) -> String {
+    let plural = |n: i64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    let minutes = secs / 60;
+    let hours = (secs as f64 / 3600.0).round() as i64;
+    match secs {
+        s if s < 1 => "Less than a second".to_string(),
+        s if s < 60 => plural(s, "second"),
+        _ if minutes == 1 => "About a minute".to_string(),
+        _ if minutes < 60 => plural(minutes, "minute"),
+        _ if hours == 1 => "About an hour".to_string(),
+        _ if hours < 48 => plural(hours, "hour"),
+        _ if hours < 24 * 7 * 2 => plural(hours / 24, "day"),
+        _ if hours < 24 * 30 * 2 => plural(hours / 24 / 7, "week"),
+        _ if hours < 24 * 365 * 2 => plural(hours / 24 / 30, "month"),
+        _ => plural(hours / 24 / 365, "year"),
+    }
+}
+
+// Actions that can change what the cache holds; exec_*, attach, resize etc. don't
+fn affects_status(action: &str) -> bool {
+    matches!(
+        action,
+        "create" | "start" | "restart" | "stop" | "kill" | "die" | "oom" | "pause" | "unpause" | "destroy" | "rename"
+    ) || action.starts_with("health_status")
+}
+
+impl StatusCache {
+    pub fn new() -> Self {
+        let (changes, _) = broadcast::channel(EVENT_BUFFER);
+        StatusCache { containers: Arc::new(RwLock::new(HashMap::new())), changes }
+    }
+
+    // Running container name → human-readable status (e.g. "Up 5 minutes")
+    pub fn running(&self) -> HashMap<String, String> {
+        let now = unix_now();
+        let containers = self.containers.read().unwrap();
+        containers
+            .iter()
+            .filter(|(_, c)| c.running)
+            .map(|(name, c)| (name.clone(), c.status_text(now)))
+            .collect()
+    }
+
+    // The app's replica containers, in replica order
+    pub fn replicas(&self, app_name: &str) -> Vec<ReplicaState> {
+        let now = unix_now();
+        let containers = self.containers.read().unwrap();
+        let mut replicas: Vec<ReplicaState> = containers
+            .iter()
+            .filter(|(_, c)| c.app.as_deref() == Some(app_name))
+            .map(|(name, c)| ReplicaState {
+                name: name.clone(),
+                running: c.running,
+                status: if c.running { c.status_text(now) } else { format!("Exited ({})", c.exit_code) },
+            })
+            .collect();
+        replicas.sort_by_key(|r| replicas::sort_key(app_name, &r.name));
+        replicas
+    }
+
+    // Container names to address the app by; just {app} if it has none yet
+    pub fn replica_names(&self, app_name: &str) -> Vec<String> {
+        let names: Vec<String> = self.replicas(app_name).into_iter().map(|r| r.name).collect();
+        if names.is_empty() { vec![app_name.to_string()] } else { names }
+    }
+
+    // Whether any replica of the app is running
+    pub fn is_running(&self, app_name: &str) -> bool {
+        let containers = self.containers.read().unwrap();
+        containers.values().any(|c| c.running && c.app.as_deref() == Some(app_name))
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StatusChange> {
+        self.changes.subscribe()
+    }
+
+    // Replaces the cache with a full listing from the runtime
+    pub async fn sync(&self, runtime: &dyn ContainerRuntime) {
+        let containers = match runtime.list_containers(true).await {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Failed to list containers: {}", e);
+                return;
+            }
+        };
+        let mut fresh = HashMap::new();
+        for c in containers {
+            if let Some(cached) = inspect(runtime, &c.name).await {
+                fresh.insert(c.name, cached);
+            }
+        }
+        *self.containers.write().unwrap() = fresh;
+    }
+
+    pub fn spawn(&self, runtime: Arc<dyn ContainerRuntime>) {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let (sink, mut events) = tokio::sync::mpsc::unbounded_channel();
+                let watcher_runtime = runtime.clone();
+                let watcher = tokio::spawn(async move { watcher_runtime.watch_events(&sink).await });
+
+                // Anything missed while disconnected is picked up here; events
+                // arriving meanwhile are queued and applied afterwards
+                cache.sync(runtime.as_ref()).await;
+                while let Some(event) = events.recv().await {
+                    cache.apply(runtime.as_ref(), event).await;
+                }
+
+                match watcher.await {
+                    Ok(Err(e)) => eprintln!("Container event stream failed: {}", e),
+                    Ok(Ok(())) => eprintln!("Container event stream ended"),
+                    Err(_) => {}
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+    }
+
+    async fn apply(&self, runtime: &dyn ContainerRuntime, event: ContainerEvent) {
+        if !affects_status(&event.action) {
+            return;
+        }
+        let previous = {
+            let mut containers = self.containers.write().unwrap();
+            let renamed = event.old_name.as_ref().and_then(|old_name| containers.remove(old_name));
+            renamed.or_else(|| containers.get(&event.name).cloned())
+        };
+        let cached = match event.action.as_str() {
+            "destroy" => None,
+            _ => inspect(runtime, &event.name).await,
+        };
+        let change = StatusChange {
+            name: event.name.clone(),
+            app: cached.as_ref().or(previous.as_ref()).and_then(|c| c.app.clone()),
+            // "health_status: healthy" → "health_status"
+            action: event.action.split(':').next().unwrap_or_default().to_string(),
+            running: cached.as_ref().is_some_and(|c| c.running),
+            state: cached.as_ref().map(|c| c.state.clone()).unwrap_or_else(|| "removed".to_string()),
+            exit_code: cached.as_ref().map(|c| c.exit_code).unwrap_or(0),
+            health: cached.as_ref().and_then(|c| c.health.clone()),
+        };
+        {
+            let mut containers = self.containers.write().unwrap();
+            match cached {
+                Some(c) => containers.insert(event.name, c),
+                None => containers.remove(&event.name),
+            };
+        }
+        // No subscribers is fine
+        let _ = self.changes.send(change);
+    }
+}
+
+async fn inspect(runtime: &dyn ContainerRuntime, name: &str) -> Option<CachedContainer> {
+    match runtime.inspect_container(name).await {
+        Ok(d) => Some(CachedContainer {
+            app: replicas::app_of(name, &d.labels).map(|a| a.to_string()),
+            started_at: parse_rfc3339(&d.started_at),
+            state: d.state,
+            running: d.running,
+            exit_code: d.exit_code,
+            health: d.health,
+        }),
+        Err(RuntimeError::NotFound(_)) => None,
+        Err(e) => {
+            eprintln!("Failed to inspect {}: {}", name, e);
+            None
+        }
+    }
+}
+
+// ── Handlers ──
+
+// Status of the app a container belongs to and the summary of the groups the
+// app is in, as list_apps and list_groups would report them now
+fn app_event(state: &AppState, change: &StatusChange) -> (serde_json::Value, Vec<serde_json::Value>) {
+    let app_name = change.app.as_deref().unwrap_or(&change.name);
+    let (status, replicas) = crate::app_status(state, app_name);
+    let mut app = serde_json::to_value(change).unwrap_or_default();
+    app["status"] = serde_json::json!(status);
+    app["replicas"] = serde_json::json!(replicas);
+
+    let groups = state.groups.read().unwrap().clone();
+    let mut affected: Vec<(String, Vec<String>)> = groups
+        .iter()
+        .filter(|g| g.containers.iter().any(|c| c == app_name))
+        .map(|g| (g.id.clone(), g.containers.clone()))
+        .collect();
+    let app_names = crate::app_dir_names();
+    if affected.is_empty() && app_names.iter().any(|a| a == app_name) {
+        let ungrouped = app_names
+            .into_iter()
+            .filter(|name| !groups.iter().any(|g| g.containers.contains(name)))
+            .collect();
+        affected.push(("default".to_string(), ungrouped));
+    }
+    let groups = affected
+        .into_iter()
+        .map(|(id, containers)| {
+            let summary = crate::compute_summary(state, &containers);
+            serde_json::json!({ "id": id, "containerSummary": summary })
+        })
+        .collect();
+    (app, groups)
+}
+
+// GET /api/events: a "snapshot" event with every running container's status,
+// then "app" and "group" events as containers change. Slow clients that fall
+// behind skip ahead rather than replaying stale changes.
+pub async fn stream_events(State(state): State<Arc<AppState>>) -> Response {
+    let mut changes = state.status.subscribe();
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(256);
+    tokio::spawn(async move {
+        let snapshot = serde_json::json!({ "running": state.status.running() });
+        if tx.send(Ok(Event::default().event("snapshot").data(snapshot.to_string()))).await.is_err() {
+            return;
+        }
+        loop {
+            // Without changes nothing is sent, so watch for the client leaving too
+            let received = tokio::select! {
+                received = changes.recv() => received,
+                _ = tx.closed() => return,
+            };
+            let change = match received {
+                Ok(change) => change,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let (app, groups) = app_event(&state, &change);
+            if tx.send(Ok(Event::default().event("app").data(app.to_string()))).await.is_err() {
+                return;
+            }
+            for group in groups {
+                if tx.send(Ok(Event::default().event("group").data(group.to_string()))).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Sse::new(ReceiverStream::new(rx))
+        .keep_alive(axum::response::sse::KeepAlive::default())
+        .into_response()
+}
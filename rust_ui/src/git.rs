@@ -0,0 +1,398 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::builds::Trigger;
+use crate::locks::LockConflict;
+use crate::manifest;
+use crate::rollout;
+use crate::AppState;
+
+// ── Git sources ──
+//
+// `POST /api/create/{app}?git_url=...&branch=...` clones the repository into
+// /apps/{app} instead of writing the starter files. The checkout itself is the
+// record of where the app came from: origin URL, branch and HEAD commit are
+// read back from it, and every build stores the commit it was built from.
+//
+// POST /api/apps/{app}/pull fetches the branch and fast-forwards the checkout,
+// optionally queueing a deploy (`?rebuild=true`) when it moved. Edits made in
+// the IDE show up as modified tracked files or new, non-ignored files; a pull
+// refuses to run over them and reports them instead. Diverged history is
+// refused the same way.
+
+const DEFAULT_GIT_TIMEOUT_SECS: u64 = 300;
+pub const APPS_ROOT: &str = "/apps";
+
+#[derive(Serialize)]
+pub struct GitInfo {
+    url: String,
+    branch: String,
+    commit: String,
+    // Modified and untracked, non-ignored files (`git status --porcelain` lines)
+    changes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PullQuery {
+    #[serde(default)]
+    rebuild: bool,
+    #[serde(default)]
+    wait: bool,
+}
+
+fn error(status: StatusCode, msg: String) -> Response {
+    (status, Json(serde_json::json!({"error": msg}))).into_response()
+}
+
+fn git_timeout() -> Duration {
+    let secs = std::env::var("GIT_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_GIT_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+// Pull outcomes the handler turns into responses
+#[derive(Debug)]
+enum PullError {
+    LocalChanges(Vec<String>),
+    Detached,
+    Fetch(String),
+    Diverged { branch: String, message: String },
+    Git(String),
+}
+
+#[derive(Debug)]
+struct Pulled {
+    branch: String,
+    previous: String,
+    commit: String,
+}
+
+fn app_dir(root: &str, app_name: &str) -> String {
+    format!("{}/{}", root, app_name)
+}
+
+fn is_checkout(dir: &str) -> bool {
+    std::path::Path::new(&format!("{}/.git", dir)).exists()
+}
+
+// Files in /apps are often owned by the container's user, which git would
+// otherwise refuse as "dubious ownership"; prompts would hang the request
+async fn git(dir: Option<&str>, args: &[&str]) -> Result<String, String> {
+    let mut cmd = tokio::process::Command::new("git");
+    cmd.args(["-c", "safe.directory=*"]);
+    if let Some(dir) = dir {
+        cmd.args(["-C", dir]);
+    }
+    cmd.args(args).env("GIT_TERMINAL_PROMPT", "0").kill_on_drop(true);
+    let output = tokio::time::timeout(git_timeout(), cmd.output())
+        .await
+        .map_err(|_| format!("git {} timed out", args[0]))?
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+// HEAD of the app's checkout, for build records; None if it isn't one
+pub fn head_commit(app_name: &str) -> Option<String> {
+    let dir = app_dir(APPS_ROOT, app_name);
+    if !is_checkout(&dir) {
+        return None;
+    }
+    let output = std::process::Command::new("git")
+        .args(["-c", "safe.directory=*", "-C", &dir, "rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+async fn local_changes(dir: &str) -> Result<Vec<String>, String> {
+    let status = git(Some(dir), &["status", "--porcelain", "--untracked-files=all"]).await?;
+    Ok(status.lines().map(|l| l.to_string()).collect())
+}
+
+async fn info(dir: &str) -> Result<GitInfo, String> {
+    Ok(GitInfo {
+        url: git(Some(dir), &["config", "--get", "remote.origin.url"]).await.unwrap_or_default(),
+        branch: git(Some(dir), &["rev-parse", "--abbrev-ref", "HEAD"]).await?,
+        commit: git(Some(dir), &["rev-parse", "HEAD"]).await?,
+        changes: local_changes(dir).await?,
+    })
+}
+
+// Clones `url` into {root}/{app}; the directory must not exist yet
+pub async fn clone_into(root: &str, app_name: &str, url: &str, branch: Option<&str>) -> Result<GitInfo, String> {
+    if url.is_empty() || url.starts_with('-') {
+        return Err("Invalid Git URL".to_string());
+    }
+    let dir = app_dir(root, app_name);
+    let mut args = vec!["clone", "--single-branch"];
+    if let Some(branch) = branch {
+        args.extend(["--branch", branch]);
+    }
+    args.extend(["--", url, &dir]);
+    if let Err(e) = git(None, &args).await {
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        return Err(format!("git clone failed: {}", e));
+    }
+    info(&dir).await
+}
+
+// Fetches the checked out branch and fast-forwards to it; never merges or
+// touches local edits
+async fn fast_forward(dir: &str) -> Result<Pulled, PullError> {
+    let before = info(dir).await.map_err(PullError::Git)?;
+    if !before.changes.is_empty() {
+        return Err(PullError::LocalChanges(before.changes));
+    }
+    if before.branch == "HEAD" {
+        return Err(PullError::Detached);
+    }
+    git(Some(dir), &["fetch", "origin", &before.branch]).await.map_err(PullError::Fetch)?;
+    if let Err(message) = git(Some(dir), &["merge", "--ff-only", "FETCH_HEAD"]).await {
+        // Files that appeared since the status check
+        if message.contains("would be overwritten") {
+            return Err(PullError::LocalChanges(local_changes(dir).await.unwrap_or_else(|_| vec![message])));
+        }
+        return Err(PullError::Diverged { branch: before.branch, message });
+    }
+    let commit = git(Some(dir), &["rev-parse", "HEAD"]).await.map_err(PullError::Git)?;
+    Ok(Pulled { branch: before.branch, previous: before.commit, commit })
+}
+
+// ── Handlers ──
+
+pub async fn get_git(Path(app_name): Path<String>) -> Response {
+    let dir = app_dir(APPS_ROOT, &app_name);
+    if !crate::valid_app_name(&app_name) || !std::path::Path::new(&dir).is_dir() {
+        return error(StatusCode::NOT_FOUND, "App not found".to_string());
+    }
+    if !is_checkout(&dir) {
+        return error(StatusCode::NOT_FOUND, "App is not a Git checkout".to_string());
+    }
+    match info(&dir).await {
+        Ok(info) => Json(info).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+pub async fn pull(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Query(query): Query<PullQuery>,
+) -> Result<Response, LockConflict> {
+    let dir = app_dir(APPS_ROOT, &app_name);
+    if !crate::valid_app_name(&app_name) || !std::path::Path::new(&dir).is_dir() {
+        return Ok(error(StatusCode::NOT_FOUND, "App not found".to_string()));
+    }
+    if !is_checkout(&dir) {
+        return Ok(error(StatusCode::BAD_REQUEST, "App is not a Git checkout".to_string()));
+    }
+    let guard = state.locks.lock(&app_name, "pull", query.wait).await?;
+
+    let pulled = match fast_forward(&dir).await {
+        Ok(pulled) => pulled,
+        Err(PullError::LocalChanges(changes)) => {
+            return Ok((StatusCode::CONFLICT, Json(serde_json::json!({
+                "error": "The checkout has local changes; commit or discard them before pulling",
+                "changes": changes,
+            })))
+            .into_response());
+        }
+        Err(PullError::Detached) => {
+            return Ok(error(StatusCode::BAD_REQUEST, "The checkout is not on a branch".to_string()));
+        }
+        Err(PullError::Fetch(e)) => return Ok(error(StatusCode::BAD_GATEWAY, format!("git fetch failed: {}", e))),
+        Err(PullError::Diverged { branch, message }) => {
+            return Ok(error(StatusCode::CONFLICT, format!("Can't fast-forward {}: {}", branch, message)));
+        }
+        Err(PullError::Git(e)) => return Ok(error(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    };
+    let updated = pulled.commit != pulled.previous;
+
+    // The deploy keeps the pull's lock, so nothing slips in between
+    let mut job_id = None;
+    if query.rebuild && updated {
+        let strategy = manifest::load(&app_name).ok().map(|m| m.deploy_strategy).unwrap_or_default();
+        let kind = rollout::job_kind(strategy, "deploy");
+        let job_state = state.clone();
+        let job_app = app_name.clone();
+        job_id = Some(state.jobs.submit(&app_name, &kind, move |log| async move {
+            let _guard = guard;
+            let build = job_state.builds.start(&job_app, Trigger::Pull);
+            let result = crate::run_deploy(job_state.clone(), job_app, strategy, &build, build.tee(log)).await;
+            build.finish(result.as_ref().map(|_| ()).map_err(|e| e.message.clone())).await;
+            result
+        }));
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "success",
+        "branch": pulled.branch,
+        "previous": pulled.previous,
+        "commit": pulled.commit,
+        "updated": updated,
+        "job_id": job_id,
+    }))
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    // A bare "origin" with one commit on main, plus a work clone to push more from
+    struct Fixture {
+        base: String,
+        origin: String,
+        work: String,
+    }
+
+    impl Fixture {
+        async fn new() -> Self {
+            let n = NEXT.fetch_add(1, Ordering::SeqCst);
+            let base = std::env::temp_dir().join(format!("git-test-{}-{}", std::process::id(), n)).display().to_string();
+            let _ = std::fs::remove_dir_all(&base);
+            std::fs::create_dir_all(format!("{}/apps", base)).unwrap();
+            let origin = format!("{}/origin.git", base);
+            let work = format!("{}/work", base);
+            git(None, &["init", "--bare", "-b", "main", &origin]).await.unwrap();
+            git(None, &["clone", &origin, &work]).await.unwrap();
+            let fixture = Fixture { base, origin, work };
+            fixture.commit(&fixture.work, "app.py", "print('v1')\n").await;
+            git(Some(&fixture.work), &["push", "origin", "HEAD:main"]).await.unwrap();
+            fixture
+        }
+
+        fn apps(&self) -> String {
+            format!("{}/apps", self.base)
+        }
+
+        async fn commit(&self, dir: &str, file: &str, content: &str) -> String {
+            std::fs::write(format!("{}/{}", dir, file), content).unwrap();
+            git(Some(dir), &["add", file]).await.unwrap();
+            git(Some(dir), &["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "-m", file])
+                .await
+                .unwrap();
+            git(Some(dir), &["rev-parse", "HEAD"]).await.unwrap()
+        }
+
+        async fn push_change(&self, content: &str) -> String {
+            let commit = self.commit(&self.work, "app.py", content).await;
+            git(Some(&self.work), &["push", "origin", "HEAD:main"]).await.unwrap();
+            commit
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.base);
+        }
+    }
+
+    #[tokio::test]
+    async fn clone_records_origin_branch_and_commit() {
+        let fx = Fixture::new().await;
+        let head = git(Some(&fx.work), &["rev-parse", "HEAD"]).await.unwrap();
+        let info = clone_into(&fx.apps(), "demo", &fx.origin, Some("main")).await.unwrap();
+        assert_eq!(info.url, fx.origin);
+        assert_eq!(info.branch, "main");
+        assert_eq!(info.commit, head);
+        assert!(info.changes.is_empty());
+        assert!(is_checkout(&app_dir(&fx.apps(), "demo")));
+    }
+
+    #[tokio::test]
+    async fn clone_refuses_option_like_urls() {
+        let fx = Fixture::new().await;
+        assert!(clone_into(&fx.apps(), "demo", "--upload-pack=x", None).await.is_err());
+        assert!(!std::path::Path::new(&app_dir(&fx.apps(), "demo")).exists());
+    }
+
+    #[tokio::test]
+    async fn pull_fast_forwards_to_the_new_commit() {
+        let fx = Fixture::new().await;
+        let cloned = clone_into(&fx.apps(), "demo", &fx.origin, None).await.unwrap();
+        let dir = app_dir(&fx.apps(), "demo");
+
+        let unchanged = fast_forward(&dir).await.unwrap();
+        assert_eq!(unchanged.commit, unchanged.previous);
+
+        let pushed = fx.push_change("print('v2')\n").await;
+        let pulled = fast_forward(&dir).await.unwrap();
+        assert_eq!(pulled.branch, "main");
+        assert_eq!(pulled.previous, cloned.commit);
+        assert_eq!(pulled.commit, pushed);
+        assert_ne!(pulled.commit, pulled.previous);
+        assert_eq!(std::fs::read_to_string(format!("{}/app.py", dir)).unwrap(), "print('v2')\n");
+    }
+
+    #[tokio::test]
+    async fn pull_refuses_local_edits() {
+        let fx = Fixture::new().await;
+        clone_into(&fx.apps(), "demo", &fx.origin, None).await.unwrap();
+        let dir = app_dir(&fx.apps(), "demo");
+        std::fs::write(format!("{}/app.py", dir), "print('edited')\n").unwrap();
+        std::fs::write(format!("{}/notes.txt", dir), "scratch\n").unwrap();
+        // Ignored files don't count
+        std::fs::write(format!("{}/.git/info/exclude", dir), "*.log\n").unwrap();
+        std::fs::write(format!("{}/debug.log", dir), "noise\n").unwrap();
+        fx.push_change("print('v2')\n").await;
+
+        match fast_forward(&dir).await {
+            Err(PullError::LocalChanges(changes)) => {
+                assert_eq!(changes, vec![" M app.py".to_string(), "?? notes.txt".to_string()])
+            }
+            other => panic!("expected local changes, got {:?}", other),
+        }
+        assert_eq!(std::fs::read_to_string(format!("{}/app.py", dir)).unwrap(), "print('edited')\n");
+    }
+
+    #[tokio::test]
+    async fn pull_refuses_new_files_upstream_would_overwrite() {
+        let fx = Fixture::new().await;
+        clone_into(&fx.apps(), "demo", &fx.origin, None).await.unwrap();
+        let dir = app_dir(&fx.apps(), "demo");
+        let pushed = fx.commit(&fx.work, "util.py", "upstream\n").await;
+        git(Some(&fx.work), &["push", "origin", "HEAD:main"]).await.unwrap();
+        std::fs::write(format!("{}/util.py", dir), "from the IDE\n").unwrap();
+
+        match fast_forward(&dir).await {
+            Err(PullError::LocalChanges(changes)) => assert_eq!(changes, vec!["?? util.py".to_string()]),
+            other => panic!("expected local changes, got {:?}", other),
+        }
+        assert_eq!(std::fs::read_to_string(format!("{}/util.py", dir)).unwrap(), "from the IDE\n");
+
+        std::fs::remove_file(format!("{}/util.py", dir)).unwrap();
+        assert_eq!(fast_forward(&dir).await.unwrap().commit, pushed);
+    }
+
+    #[tokio::test]
+    async fn pull_refuses_diverged_history() {
+        let fx = Fixture::new().await;
+        clone_into(&fx.apps(), "demo", &fx.origin, None).await.unwrap();
+        let dir = app_dir(&fx.apps(), "demo");
+        let local = fx.commit(&dir, "local.txt", "local\n").await;
+        fx.push_change("print('v2')\n").await;
+
+        match fast_forward(&dir).await {
+            Err(PullError::Diverged { branch, .. }) => assert_eq!(branch, "main"),
+            other => panic!("expected diverged history, got {:?}", other),
+        }
+        assert_eq!(git(Some(&dir), &["rev-parse", "HEAD"]).await.unwrap(), local);
+    }
+}
@@ -0,0 +1,263 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use crate::manifest::{self, DeployStrategy};
+use crate::{rollout, AppState};
+
+// ── Scale to zero ──
+//
+// Apps with `[scale] idle_timeout` in mcphub.toml are stopped once no request
+// has passed /api/verify for that long. A sleeping app keeps a stand-in
+// Traefik router (file provider, TRAEFIK_DYNAMIC_DIR) so its next API request
+// still reaches /api/verify, which starts the container again and holds the
+// request until the app is ready. If that takes longer than WAKE_TIMEOUT_SECS
+// the request gets a 503 with Retry-After while the start carries on.
+
+const SLEEPING_PATH: &str = "/apps/sleeping.json";
+const WAKE_ROUTES_FILE: &str = "scale_to_zero.yml";
+const DEFAULT_TRAEFIK_DYNAMIC_DIR: &str = "/manager/traefik";
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+const DEFAULT_WAKE_TIMEOUT_SECS: u64 = 30;
+const RETRY_AFTER_SECS: u64 = 5;
+
+// Outcome of a wake, shared by every request waiting on it
+type WakeResult = Option<Result<(), String>>;
+
+#[derive(Clone)]
+pub struct IdleManager {
+    // Last request per app with an idle timeout (only apps the reaper tracks)
+    last_used: Arc<RwLock<HashMap<String, Instant>>>,
+    // Apps stopped for being idle; they wake on their next request
+    sleeping: Arc<RwLock<BTreeSet<String>>>,
+    waking: Arc<Mutex<HashMap<String, watch::Receiver<WakeResult>>>>,
+    wake_timeout: Duration,
+    dynamic_dir: String,
+}
+
+fn save_sleeping(sleeping: &BTreeSet<String>) -> Result<(), std::io::Error> {
+    let json = serde_json::to_string_pretty(sleeping).map_err(std::io::Error::other)?;
+    std::fs::write(SLEEPING_PATH, json)
+}
+
+impl IdleManager {
+    pub fn from_env() -> Self {
+        let sleeping = std::fs::read_to_string(SLEEPING_PATH)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        let wake_timeout = std::env::var("WAKE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_WAKE_TIMEOUT_SECS);
+        IdleManager {
+            last_used: Arc::new(RwLock::new(HashMap::new())),
+            sleeping: Arc::new(RwLock::new(sleeping)),
+            waking: Arc::new(Mutex::new(HashMap::new())),
+            wake_timeout: Duration::from_secs(wake_timeout),
+            dynamic_dir: std::env::var("TRAEFIK_DYNAMIC_DIR")
+                .unwrap_or_else(|_| DEFAULT_TRAEFIK_DYNAMIC_DIR.to_string()),
+        }
+    }
+
+    pub fn is_sleeping(&self, app_name: &str) -> bool {
+        self.sleeping.read().unwrap().contains(app_name)
+    }
+
+    fn touch(&self, app_name: &str) {
+        if let Some(at) = self.last_used.write().unwrap().get_mut(app_name) {
+            *at = Instant::now();
+        }
+    }
+
+    // Explicitly stopped or deleted apps stay down instead of waking on requests
+    pub fn forget(&self, state: &AppState, app_name: &str) {
+        self.last_used.write().unwrap().remove(app_name);
+        if self.set_sleeping(app_name, false) {
+            self.write_wake_routes(state);
+        }
+    }
+
+    // Returns whether anything changed
+    fn set_sleeping(&self, app_name: &str, sleeping: bool) -> bool {
+        let mut set = self.sleeping.write().unwrap();
+        let changed = if sleeping { set.insert(app_name.to_string()) } else { set.remove(app_name) };
+        if changed {
+            let _ = save_sleeping(&set);
+        }
+        changed
+    }
+
+    // Rewrites the stand-in routers for all sleeping apps
+    pub fn write_wake_routes(&self, state: &AppState) {
+        let mut http = serde_json::json!({ "routers": {}, "middlewares": {}, "services": {} });
+        for app_name in self.sleeping.read().unwrap().iter() {
+            let manifest = manifest::load(app_name).unwrap_or_default();
+            let config = crate::app_route_spec(state, app_name, &manifest).wake_config();
+            for section in ["routers", "middlewares", "services"] {
+                if let (Some(all), Some(entries)) = (http[section].as_object_mut(), config[section].as_object()) {
+                    all.extend(entries.clone());
+                }
+            }
+        }
+        // YAML is a superset of JSON, and the file provider only accepts .yml/.toml
+        let json = serde_json::to_string_pretty(&serde_json::json!({ "http": http })).unwrap_or_default();
+        let _ = std::fs::create_dir_all(&self.dynamic_dir);
+        if let Err(e) = std::fs::write(format!("{}/{}", self.dynamic_dir, WAKE_ROUTES_FILE), json) {
+            eprintln!("Failed to write Traefik wake routes: {}", e);
+        }
+    }
+
+    pub fn spawn(&self, state: Arc<AppState>) {
+        self.write_wake_routes(&state);
+        let idle = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                idle.check(&state).await;
+            }
+        });
+    }
+
+    async fn check(&self, state: &AppState) {
+        let mut timeouts = HashMap::new();
+        for app_name in crate::app_dir_names() {
+            if let Some(secs) = manifest::load(&app_name).ok().and_then(|m| m.idle_timeout_secs) {
+                timeouts.insert(app_name, Duration::from_secs(secs));
+            }
+        }
+
+        let mut idle_apps = Vec::new();
+        {
+            let mut last_used = self.last_used.write().unwrap();
+            // Only running apps are tracked; a fresh start gets the full timeout
+            last_used.retain(|app, _| timeouts.contains_key(app) && state.status.is_running(app));
+            for (app_name, timeout) in &timeouts {
+                if !state.status.is_running(app_name) {
+                    continue;
+                }
+                let at = last_used.entry(app_name.clone()).or_insert_with(Instant::now);
+                if at.elapsed() >= *timeout {
+                    idle_apps.push(app_name.clone());
+                }
+            }
+        }
+
+        // Deployed or started by other means while asleep
+        let woken: Vec<String> = self.sleeping.read().unwrap()
+            .iter()
+            .filter(|app| state.status.is_running(app) || !timeouts.contains_key(*app))
+            .cloned()
+            .collect();
+        let mut changed = false;
+        for app_name in woken {
+            if !self.waking.lock().unwrap().contains_key(&app_name) {
+                changed |= self.set_sleeping(&app_name, false);
+            }
+        }
+
+        for app_name in idle_apps {
+            // Never interrupt a deploy, rebuild or wake in progress
+            let Ok(_guard) = state.locks.try_acquire(&app_name, "idle stop") else { continue };
+            // Marked first so a request racing the stop waits for the lock and wakes it again
+            self.set_sleeping(&app_name, true);
+            self.write_wake_routes(state);
+            self.last_used.write().unwrap().remove(&app_name);
+            match crate::replicas::remove_all(state.runtime.as_ref(), &app_name).await {
+                Ok(()) => println!("Stopped {} after its idle timeout", app_name),
+                Err(e) => {
+                    eprintln!("Failed to stop idle app {}: {}", app_name, e);
+                    changed |= self.set_sleeping(&app_name, false);
+                }
+            }
+        }
+        if changed {
+            self.write_wake_routes(state);
+        }
+    }
+
+    // Starts a sleeping app, joining a start already in progress
+    fn wake(&self, state: &Arc<AppState>, app_name: &str) -> watch::Receiver<WakeResult> {
+        let mut waking = self.waking.lock().unwrap();
+        if let Some(rx) = waking.get(app_name) {
+            return rx.clone();
+        }
+        let (tx, rx) = watch::channel(None);
+        waking.insert(app_name.to_string(), rx.clone());
+
+        let state = state.clone();
+        let app_name = app_name.to_string();
+        tokio::spawn(async move {
+            let result = {
+                let _guard = state.locks.acquire(&app_name, "wake").await;
+                if state.idle.is_sleeping(&app_name) {
+                    println!("Waking {} for an incoming request", app_name);
+                    start(&state, &app_name).await
+                } else {
+                    Ok(())
+                }
+            };
+            let idle = &state.idle;
+            match &result {
+                Ok(()) => {
+                    if idle.set_sleeping(&app_name, false) {
+                        idle.write_wake_routes(&state);
+                    }
+                    idle.last_used.write().unwrap().insert(app_name.clone(), Instant::now());
+                }
+                Err(e) => eprintln!("Failed to wake {}: {}", app_name, e),
+            }
+            idle.waking.lock().unwrap().remove(&app_name);
+            let _ = tx.send(Some(result));
+        });
+        rx
+    }
+}
+
+// Same container a deploy would run, from the last built image
+async fn start(state: &AppState, app_name: &str) -> Result<(), String> {
+    let manifest = manifest::load(app_name).map_err(|errors| crate::manifest_error_text(&errors))?;
+    let spec = crate::app_run_spec(state, app_name, &manifest)?;
+    crate::ensure_code_server_host_dirs(app_name);
+    let (log, _) = tokio::sync::mpsc::unbounded_channel();
+    let readiness = rollout::roll_out(
+        state.runtime.as_ref(), app_name, &manifest, spec, DeployStrategy::Recreate, state.readiness_timeout, &log, &log,
+    ).await?;
+    if readiness.is_ready() {
+        Ok(())
+    } else {
+        Err(readiness.summary())
+    }
+}
+
+fn unavailable(message: String) -> Response {
+    let mut resp = (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({"error": message}))).into_response();
+    resp.headers_mut().insert(header::RETRY_AFTER, RETRY_AFTER_SECS.into());
+    resp
+}
+
+// Called by /api/verify for every authorized request; Err holds the response
+// to send instead when a sleeping app cannot be woken in time
+pub async fn on_request(state: &Arc<AppState>, app_name: &str) -> Result<(), Response> {
+    state.idle.touch(app_name);
+    if !state.idle.is_sleeping(app_name) {
+        return Ok(());
+    }
+    let mut rx = state.idle.wake(state, app_name);
+    match tokio::time::timeout(state.idle.wake_timeout, rx.wait_for(|r| r.is_some())).await {
+        Ok(Ok(result)) => match result.clone().unwrap_or(Ok(())) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(unavailable(format!("Failed to start {}: {}", app_name, e))),
+        },
+        Ok(Err(_)) => Err(unavailable(format!("Failed to start {}", app_name))),
+        Err(_) => Err(unavailable(format!("{} is starting, retry shortly", app_name))),
+    }
+}
@@ -0,0 +1,497 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+use crate::runtime::LogSink;
+use crate::{now_iso8601, AppState};
+
+// ── Background jobs (deploy builds/runs) ──
+
+const MAX_JOB_OUTPUT_LINES: usize = 5000;
+const MAX_FINISHED_JOBS: usize = 200;
+
+tokio::task_local! {
+    // Containers the running job has started, so a cancel removes only those
+    static STARTED: Arc<Mutex<Vec<String>>>;
+}
+
+// Called right before a job creates a container; no-op outside jobs
+pub fn record_started(name: &str) {
+    let _ = STARTED.try_with(|started| started.lock().unwrap().push(name.to_string()));
+}
+
+// The container is serving now and stays if the job is cancelled later
+pub fn record_settled(name: &str) {
+    let _ = STARTED.try_with(|started| started.lock().unwrap().retain(|n| n != name));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Job {
+    pub id: String,
+    pub app_name: String,
+    pub kind: String,
+    pub status: JobStatus,
+    pub output: Vec<String>,
+    pub error: Option<String>,
+    // Structured outcome reported by the job (e.g. deploy readiness)
+    pub result: Option<serde_json::Value>,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+// Failure message plus optional structured detail for `Job::result`
+#[derive(Debug)]
+pub struct JobError {
+    pub message: String,
+    pub result: Option<serde_json::Value>,
+}
+
+impl From<String> for JobError {
+    fn from(message: String) -> Self {
+        JobError { message, result: None }
+    }
+}
+
+struct JobEntry {
+    job: Job,
+    handle: Option<JoinHandle<()>>,
+    started: Arc<Mutex<Vec<String>>>,
+    seq: u64,
+}
+
+// A job stopped by cancel(): wait() for it to halt, then clean up what it started
+pub struct Cancelled {
+    pub job: Job,
+    handle: Option<JoinHandle<()>>,
+    started: Arc<Mutex<Vec<String>>>,
+}
+
+impl Cancelled {
+    // Containers the job started before it was aborted
+    pub async fn wait(self) -> Vec<String> {
+        if let Some(handle) = self.handle {
+            let _ = handle.await;
+        }
+        self.started.lock().unwrap().clone()
+    }
+}
+
+#[derive(Clone)]
+pub struct JobManager {
+    jobs: Arc<RwLock<HashMap<String, JobEntry>>>,
+    slots: Arc<Semaphore>,
+}
+
+impl JobManager {
+    pub fn new(max_concurrent: usize) -> Self {
+        JobManager {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            slots: Arc::new(Semaphore::new(max_concurrent.max(1))),
+        }
+    }
+
+    // Queues `work` and returns the job ID immediately. Lines sent to the
+    // provided sink are captured as the job's output.
+    pub fn submit<F, Fut>(&self, app_name: &str, kind: &str, work: F) -> String
+    where
+        F: FnOnce(LogSink) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Option<serde_json::Value>, JobError>> + Send + 'static,
+    {
+        let id = format!("{:016x}", rand::random::<u64>());
+        let started: Arc<Mutex<Vec<String>>> = Arc::default();
+        {
+            let mut jobs = self.jobs.write().unwrap();
+            let seq = jobs.values().map(|e| e.seq).max().unwrap_or(0) + 1;
+            jobs.insert(id.clone(), JobEntry {
+                job: Job {
+                    id: id.clone(),
+                    app_name: app_name.to_string(),
+                    kind: kind.to_string(),
+                    status: JobStatus::Queued,
+                    output: Vec::new(),
+                    error: None,
+                    result: None,
+                    created_at: now_iso8601(),
+                    started_at: None,
+                    finished_at: None,
+                },
+                handle: None,
+                started: started.clone(),
+                seq,
+            });
+        }
+
+        let jobs = self.jobs.clone();
+        let slots = self.slots.clone();
+        let job_id = id.clone();
+        let handle = tokio::spawn(async move {
+            let _permit = slots.acquire_owned().await;
+            if !update_job(&jobs, &job_id, |job| {
+                job.status = JobStatus::Running;
+                job.started_at = Some(now_iso8601());
+            }) {
+                return;
+            }
+
+            let (sink, mut output) = tokio::sync::mpsc::unbounded_channel::<String>();
+            let collector_jobs = jobs.clone();
+            let collector_id = job_id.clone();
+            let collector = tokio::spawn(async move {
+                while let Some(line) = output.recv().await {
+                    update_job(&collector_jobs, &collector_id, |job| {
+                        job.output.push(line);
+                        if job.output.len() > MAX_JOB_OUTPUT_LINES {
+                            let excess = job.output.len() - MAX_JOB_OUTPUT_LINES;
+                            job.output.drain(..excess);
+                        }
+                    });
+                }
+            });
+
+            let result = STARTED.scope(started, work(sink)).await;
+            let _ = collector.await;
+
+            update_job(&jobs, &job_id, |job| {
+                match result {
+                    Ok(detail) => {
+                        job.status = JobStatus::Succeeded;
+                        job.result = detail;
+                    }
+                    Err(e) => {
+                        job.status = JobStatus::Failed;
+                        job.error = Some(e.message);
+                        job.result = e.result;
+                    }
+                }
+                job.finished_at = Some(now_iso8601());
+            });
+        });
+
+        let mut jobs = self.jobs.write().unwrap();
+        if let Some(entry) = jobs.get_mut(&id) {
+            entry.handle = Some(handle);
+        }
+        prune_finished(&mut jobs);
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<Job> {
+        self.jobs.read().unwrap().get(id).map(|e| e.job.clone())
+    }
+
+    // Newest first, optionally restricted to one app
+    pub fn list(&self, app_name: Option<&str>) -> Vec<Job> {
+        let jobs = self.jobs.read().unwrap();
+        let mut entries: Vec<&JobEntry> = jobs
+            .values()
+            .filter(|e| app_name.is_none_or(|a| e.job.app_name == a))
+            .collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.seq));
+        entries.into_iter().map(|e| e.job.clone()).collect()
+    }
+
+    // Returns None if the job does not exist; Err with the final status if it already finished
+    pub fn cancel(&self, id: &str) -> Option<Result<Cancelled, JobStatus>> {
+        let mut jobs = self.jobs.write().unwrap();
+        let entry = jobs.get_mut(id)?;
+        if entry.job.status.is_finished() {
+            return Some(Err(entry.job.status));
+        }
+        let handle = entry.handle.take();
+        if let Some(handle) = &handle {
+            handle.abort();
+        }
+        entry.job.status = JobStatus::Cancelled;
+        entry.job.error = Some("Cancelled by user".to_string());
+        entry.job.finished_at = Some(now_iso8601());
+        Some(Ok(Cancelled { job: entry.job.clone(), handle, started: entry.started.clone() }))
+    }
+}
+
+// Applies `f` to a job that is still in progress; returns false once it has finished
+fn update_job(
+    jobs: &RwLock<HashMap<String, JobEntry>>,
+    id: &str,
+    f: impl FnOnce(&mut Job),
+) -> bool {
+    let mut jobs = jobs.write().unwrap();
+    match jobs.get_mut(id) {
+        Some(entry) if !entry.job.status.is_finished() => {
+            f(&mut entry.job);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn prune_finished(jobs: &mut HashMap<String, JobEntry>) {
+    let mut finished: Vec<(u64, String)> = jobs
+        .iter()
+        .filter(|(_, e)| e.job.status.is_finished())
+        .map(|(id, e)| (e.seq, id.clone()))
+        .collect();
+    if finished.len() <= MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort();
+    let excess = finished.len() - MAX_FINISHED_JOBS;
+    for (_, id) in finished.into_iter().take(excess) {
+        jobs.remove(&id);
+    }
+}
+
+// Job kinds cancel_job refuses
+const UNCANCELLABLE: &[&str] = &["rename"];
+
+fn refuses_cancel(job: &Job) -> bool {
+    UNCANCELLABLE.contains(&job.kind.as_str()) && !job.status.is_finished()
+}
+
+fn status_label(status: JobStatus) -> &'static str {
+    match status {
+        JobStatus::Queued => "queued",
+        JobStatus::Running => "running",
+        JobStatus::Succeeded => "succeeded",
+        JobStatus::Failed => "failed",
+        JobStatus::Cancelled => "cancelled",
+    }
+}
+
+// ── Job endpoints ──
+
+#[derive(Deserialize)]
+pub struct JobListQuery {
+    app: Option<String>,
+}
+
+pub async fn list_jobs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<JobListQuery>,
+) -> Json<Vec<Job>> {
+    Json(state.jobs.list(query.app.as_deref()))
+}
+
+pub async fn get_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match state.jobs.get(&id) {
+        Some(job) => Json(job).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Job not found"}))).into_response(),
+    }
+}
+
+pub async fn cancel_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    // A rename moves state in several steps and undoes them itself on failure;
+    // stopping it in between would leave the app half under each name
+    if state.jobs.get(&id).is_some_and(|job| refuses_cancel(&job)) {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": "This job can't be cancelled; it rolls back on its own if it fails"})),
+        )
+            .into_response();
+    }
+    match state.jobs.cancel(&id) {
+        Some(Ok(cancelled)) => {
+            // Only containers this job started: a queued job or one still building
+            // never touched the running app. Recreate leaves its replacement behind,
+            // blue/green only its _green stand-in; replicas it already rolled stay.
+            let job = cancelled.job.clone();
+            let started = cancelled.wait().await;
+            for name in &started {
+                let _ = state.runtime.remove_container(name).await;
+            }
+            Json(serde_json::json!({"status": "cancelled", "job": job, "removed_containers": started})).into_response()
+        }
+        Some(Err(status)) => (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({"error": format!("Job already {}", status_label(status))})),
+        )
+            .into_response(),
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Job not found"}))).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // Polls until the job reaches a status accepted by `done`
+    async fn wait_for(jobs: &JobManager, id: &str, done: impl Fn(&Job) -> bool) -> Job {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let job = jobs.get(id).unwrap();
+                if done(&job) {
+                    return job;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("job did not get there in time")
+    }
+
+    async fn finished(jobs: &JobManager, id: &str) -> Job {
+        wait_for(jobs, id, |job| job.status.is_finished()).await
+    }
+
+    #[tokio::test]
+    async fn captures_output_and_result() {
+        let jobs = JobManager::new(2);
+        let ok = jobs.submit("demo", "deploy", |log| async move {
+            let _ = log.send("building".to_string());
+            let _ = log.send("done".to_string());
+            Ok(Some(serde_json::json!({"ready": true})))
+        });
+        let failed = jobs.submit("demo", "rebuild", |_| async move {
+            Err(JobError { message: "build failed".to_string(), result: Some(serde_json::json!({"step": "build"})) })
+        });
+
+        let job = finished(&jobs, &ok).await;
+        assert_eq!(job.status, JobStatus::Succeeded);
+        assert_eq!(job.output, vec!["building", "done"]);
+        assert_eq!(job.result, Some(serde_json::json!({"ready": true})));
+        assert!(job.started_at.is_some() && job.finished_at.is_some());
+
+        let job = finished(&jobs, &failed).await;
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.error.as_deref(), Some("build failed"));
+        assert_eq!(job.result, Some(serde_json::json!({"step": "build"})));
+
+        let ids: Vec<String> = jobs.list(Some("demo")).into_iter().map(|j| j.id).collect();
+        assert_eq!(ids, vec![failed, ok]);
+        assert!(jobs.list(Some("other")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_running_job_reports_what_it_started() {
+        let jobs = JobManager::new(2);
+        let id = jobs.submit("demo", "deploy", |_| async move {
+            record_started("demo");
+            record_settled("demo");
+            record_started("demo_green");
+            std::future::pending::<()>().await;
+            Ok(None)
+        });
+        wait_for(&jobs, &id, |job| job.status == JobStatus::Running).await;
+
+        let cancelled = jobs.cancel(&id).unwrap().unwrap();
+        assert_eq!(cancelled.job.status, JobStatus::Cancelled);
+        assert_eq!(cancelled.wait().await, vec!["demo_green"]);
+        let job = jobs.get(&id).unwrap();
+        assert_eq!(job.status, JobStatus::Cancelled);
+        assert_eq!(job.error.as_deref(), Some("Cancelled by user"));
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_queued_job_leaves_the_running_one() {
+        let jobs = JobManager::new(1);
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let running = jobs.submit("demo", "deploy", |_| async move {
+            let _ = released.await;
+            Ok(None)
+        });
+        let queued = jobs.submit("demo", "deploy", |_| async move {
+            record_started("demo");
+            Ok(None)
+        });
+        wait_for(&jobs, &running, |job| job.status == JobStatus::Running).await;
+        assert_eq!(jobs.get(&queued).unwrap().status, JobStatus::Queued);
+
+        assert!(jobs.cancel(&queued).unwrap().unwrap().wait().await.is_empty());
+        let _ = release.send(());
+        assert_eq!(finished(&jobs, &running).await.status, JobStatus::Succeeded);
+        assert_eq!(jobs.get(&queued).unwrap().status, JobStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn finished_and_unknown_jobs_are_not_cancelled() {
+        let jobs = JobManager::new(1);
+        let id = jobs.submit("demo", "deploy", |_| async move { Ok(None) });
+        finished(&jobs, &id).await;
+        assert!(matches!(jobs.cancel(&id), Some(Err(JobStatus::Succeeded))));
+        assert_eq!(jobs.get(&id).unwrap().status, JobStatus::Succeeded);
+        assert!(jobs.cancel("missing").is_none());
+    }
+
+    #[tokio::test]
+    async fn renames_refuse_cancel_until_they_finish() {
+        let jobs = JobManager::new(2);
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let rename = jobs.submit("demo", "rename", |_| async move {
+            let _ = released.await;
+            Ok(None)
+        });
+        let deploy = jobs.submit("demo", "deploy", |_| std::future::pending());
+        let running = wait_for(&jobs, &rename, |job| job.status == JobStatus::Running).await;
+        assert!(refuses_cancel(&running));
+        assert!(!refuses_cancel(&jobs.get(&deploy).unwrap()));
+
+        let _ = release.send(());
+        assert!(!refuses_cancel(&finished(&jobs, &rename).await));
+    }
+
+    fn entry(seq: u64, status: JobStatus) -> (String, JobEntry) {
+        let id = format!("job-{}", seq);
+        let job = Job {
+            id: id.clone(),
+            app_name: "demo".to_string(),
+            kind: "deploy".to_string(),
+            status,
+            output: Vec::new(),
+            error: None,
+            result: None,
+            created_at: now_iso8601(),
+            started_at: None,
+            finished_at: None,
+        };
+        (id, JobEntry { job, handle: None, started: Arc::default(), seq })
+    }
+
+    #[test]
+    fn prune_finished_drops_the_oldest_finished_jobs() {
+        let total = MAX_FINISHED_JOBS as u64 + 2;
+        let mut jobs: HashMap<String, JobEntry> = (1..=total).map(|seq| entry(seq, JobStatus::Succeeded)).collect();
+        // Older than every finished job, but still running
+        jobs.extend([entry(0, JobStatus::Running)]);
+
+        prune_finished(&mut jobs);
+        assert_eq!(jobs.len(), MAX_FINISHED_JOBS + 1);
+        assert!(jobs.contains_key("job-0"));
+        assert!(!jobs.contains_key("job-1") && !jobs.contains_key("job-2"));
+        assert!(jobs.contains_key("job-3") && jobs.contains_key(&format!("job-{}", total)));
+
+        prune_finished(&mut jobs);
+        assert_eq!(jobs.len(), MAX_FINISHED_JOBS + 1);
+    }
+}
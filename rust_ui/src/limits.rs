@@ -0,0 +1,164 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+use crate::manifest::{self, Resources};
+use crate::AppState;
+
+// ── Resource limits (memory / CPU / PIDs) ──
+//
+// Each limit resolves field by field: the app's own hub setting, then its
+// mcphub.toml [resources], then the first group containing the app. A
+// hub-wide ceiling (MAX_APP_MEMORY / MAX_APP_CPUS / MAX_APP_PIDS) caps every
+// value and is used as the limit when nothing else sets one.
+
+#[derive(Debug, Clone, Default)]
+pub struct ResourceCeiling {
+    memory: Option<(String, i64)>,
+    cpus: Option<f64>,
+    pids: Option<i64>,
+}
+
+impl ResourceCeiling {
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let ceiling = ResourceCeiling {
+            memory: var("MAX_APP_MEMORY").and_then(|v| manifest::parse_memory(&v).map(|b| (v, b))),
+            cpus: var("MAX_APP_CPUS").and_then(|v| v.parse().ok()).filter(|c: &f64| *c > 0.0),
+            pids: var("MAX_APP_PIDS").and_then(|v| v.parse().ok()).filter(|p: &i64| *p >= 1),
+        };
+        if !ceiling.as_resources().is_empty() {
+            println!("Resource ceiling: {:?}", ceiling.as_resources());
+        }
+        ceiling
+    }
+
+    pub fn as_resources(&self) -> Resources {
+        Resources {
+            memory: self.memory.as_ref().map(|(v, _)| v.clone()),
+            cpus: self.cpus,
+            pids: self.pids,
+        }
+    }
+
+    pub fn check(&self, res: &Resources) -> Result<(), String> {
+        validate(res)?;
+        if let (Some(memory), Some((max, max_bytes))) = (&res.memory, &self.memory)
+            && manifest::parse_memory(memory).unwrap_or(0) > *max_bytes
+        {
+            return Err(format!("memory {} exceeds the hub limit of {}", memory, max));
+        }
+        if let (Some(cpus), Some(max)) = (res.cpus, self.cpus)
+            && cpus > max
+        {
+            return Err(format!("cpus {} exceeds the hub limit of {}", cpus, max));
+        }
+        if let (Some(pids), Some(max)) = (res.pids, self.pids)
+            && pids > max
+        {
+            return Err(format!("pids {} exceeds the hub limit of {}", pids, max));
+        }
+        Ok(())
+    }
+}
+
+impl Resources {
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_none() && self.cpus.is_none() && self.pids.is_none()
+    }
+
+    // Fills unset fields from `fallback`
+    pub fn or(self, fallback: &Resources) -> Resources {
+        Resources {
+            memory: self.memory.or_else(|| fallback.memory.clone()),
+            cpus: self.cpus.or(fallback.cpus),
+            pids: self.pids.or(fallback.pids),
+        }
+    }
+}
+
+pub fn validate(res: &Resources) -> Result<(), String> {
+    if let Some(memory) = &res.memory
+        && manifest::parse_memory(memory).is_none()
+    {
+        return Err(format!("memory '{}' is not a size like \"512m\" or \"2g\"", memory));
+    }
+    if let Some(cpus) = res.cpus
+        && (!cpus.is_finite() || cpus <= 0.0)
+    {
+        return Err("cpus must be greater than 0".into());
+    }
+    if let Some(pids) = res.pids
+        && pids < 1
+    {
+        return Err("pids must be at least 1".into());
+    }
+    Ok(())
+}
+
+// Group defaults for an app; earlier groups win when the app is in several
+pub fn group_resources(state: &AppState, app_name: &str) -> Resources {
+    state.groups.read().unwrap()
+        .iter()
+        .filter(|g| g.containers.iter().any(|c| c == app_name))
+        .fold(Resources::default(), |acc, g| acc.or(&g.resources))
+}
+
+pub fn app_resources(state: &AppState, app_name: &str) -> Resources {
+    state.resources_config.read().unwrap()
+        .get(app_name)
+        .cloned()
+        .unwrap_or_default()
+}
+
+// Limits applied to the container, checked against the hub ceiling
+pub fn effective(state: &AppState, app_name: &str, manifest: &manifest::AppManifest) -> Result<Resources, String> {
+    let resolved = app_resources(state, app_name)
+        .or(&manifest.resources)
+        .or(&group_resources(state, app_name));
+    state.ceiling.check(&resolved).map_err(|e| format!("Resource limits for {}: {}", app_name, e))?;
+    Ok(resolved.or(&state.ceiling.as_resources()))
+}
+
+// ── Handlers ──
+
+pub async fn get_resources(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> Json<serde_json::Value> {
+    let manifest = manifest::load(&app_name).unwrap_or_default();
+    let effective = effective(&state, &app_name, &manifest);
+    Json(serde_json::json!({
+        "app": app_resources(&state, &app_name),
+        "manifest": manifest.resources,
+        "group": group_resources(&state, &app_name),
+        "ceiling": state.ceiling.as_resources(),
+        "effective": effective.as_ref().ok(),
+        "error": effective.err(),
+    }))
+}
+
+pub async fn set_resources(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Json(resources): Json<Resources>,
+) -> Response {
+    if let Err(e) = state.ceiling.check(&resources) {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": e}))).into_response();
+    }
+    let mut config = state.resources_config.write().unwrap();
+    if resources.is_empty() {
+        config.remove(&app_name);
+    } else {
+        config.insert(app_name, resources);
+    }
+    // Limits are fixed at container creation, so changes apply on the next deploy/rebuild
+    match crate::save_resources_config(&config) {
+        Ok(_) => Json(serde_json::json!({"status": "ok"})).into_response(),
+        Err(e) => Json(serde_json::json!({"error": format!("Failed to save: {}", e)})).into_response(),
+    }
+}
@@ -0,0 +1,213 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::now_iso8601;
+
+// ── Per-app operation locks ──
+//
+// Deploy, rebuild, stop, delete and password changes all recreate or touch the
+// same container, so only one of them may run per app at a time.
+
+#[derive(Clone)]
+struct Holder {
+    operation: String,
+    since: String,
+}
+
+#[derive(Default)]
+struct LockSlot {
+    mutex: Arc<tokio::sync::Mutex<()>>,
+    holder: Mutex<Option<Holder>>,
+}
+
+#[derive(Clone, Default)]
+pub struct AppLocks {
+    slots: Arc<Mutex<HashMap<String, Arc<LockSlot>>>>,
+}
+
+pub struct AppLockGuard {
+    slot: Arc<LockSlot>,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl Drop for AppLockGuard {
+    fn drop(&mut self) {
+        // Clear the holder before the mutex guard field is released
+        *self.slot.holder.lock().unwrap() = None;
+    }
+}
+
+#[derive(Debug)]
+pub struct LockConflict {
+    pub app_name: String,
+    pub operation: String,
+    pub since: String,
+}
+
+impl IntoResponse for LockConflict {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": format!("App '{}' is busy: {} in progress", self.app_name, self.operation),
+                "operation": self.operation,
+                "since": self.since,
+            })),
+        )
+            .into_response()
+    }
+}
+
+// `?wait=true` queues behind the current holder instead of failing with 409
+#[derive(Deserialize, Default)]
+pub struct WaitQuery {
+    #[serde(default)]
+    pub wait: bool,
+}
+
+impl AppLocks {
+    fn slot(&self, app_name: &str) -> Arc<LockSlot> {
+        self.slots
+            .lock()
+            .unwrap()
+            .entry(app_name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn hold(slot: Arc<LockSlot>, guard: OwnedMutexGuard<()>, operation: &str) -> AppLockGuard {
+        *slot.holder.lock().unwrap() = Some(Holder {
+            operation: operation.to_string(),
+            since: now_iso8601(),
+        });
+        AppLockGuard { slot, _guard: guard }
+    }
+
+    pub fn try_acquire(&self, app_name: &str, operation: &str) -> Result<AppLockGuard, LockConflict> {
+        let slot = self.slot(app_name);
+        match slot.mutex.clone().try_lock_owned() {
+            Ok(guard) => Ok(Self::hold(slot, guard, operation)),
+            Err(_) => {
+                let holder = slot.holder.lock().unwrap().clone();
+                let (operation, since) = holder
+                    .map(|h| (h.operation, h.since))
+                    .unwrap_or_else(|| ("another operation".to_string(), now_iso8601()));
+                Err(LockConflict { app_name: app_name.to_string(), operation, since })
+            }
+        }
+    }
+
+    pub async fn acquire(&self, app_name: &str, operation: &str) -> AppLockGuard {
+        let slot = self.slot(app_name);
+        let guard = slot.mutex.clone().lock_owned().await;
+        Self::hold(slot, guard, operation)
+    }
+
+    // Both apps' locks, always taken in name order so two waiting callers
+    // locking the same pair the other way round can't deadlock
+    pub async fn lock_pair(&self, a: &str, b: &str, operation: &str, wait: bool) -> Result<[AppLockGuard; 2], LockConflict> {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        let first = self.lock(first, operation, wait).await?;
+        let second = self.lock(second, operation, wait).await?;
+        Ok([first, second])
+    }
+
+    // Waits or fails fast depending on the caller's `wait` flag
+    pub async fn lock(&self, app_name: &str, operation: &str, wait: bool) -> Result<AppLockGuard, LockConflict> {
+        if wait {
+            Ok(self.acquire(app_name, operation).await)
+        } else {
+            self.try_acquire(app_name, operation)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn try_acquire_names_the_holder() {
+        let locks = AppLocks::default();
+        let guard = locks.try_acquire("demo", "deploy").unwrap();
+        let conflict = locks.try_acquire("demo", "stop").err().unwrap();
+        assert_eq!(conflict.app_name, "demo");
+        assert_eq!(conflict.operation, "deploy");
+        assert_eq!(conflict.into_response().status(), StatusCode::CONFLICT);
+        // Other apps are unaffected
+        assert!(locks.try_acquire("other", "stop").is_ok());
+
+        drop(guard);
+        assert!(locks.try_acquire("demo", "stop").is_ok());
+    }
+
+    #[tokio::test]
+    async fn acquire_waits_for_the_holder() {
+        let locks = AppLocks::default();
+        let guard = locks.try_acquire("demo", "deploy").unwrap();
+        let waiter = tokio::spawn({
+            let locks = locks.clone();
+            async move { locks.acquire("demo", "rebuild").await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        drop(guard);
+        let guard = tokio::time::timeout(TIMEOUT, waiter).await.unwrap().unwrap();
+        assert_eq!(locks.try_acquire("demo", "stop").err().unwrap().operation, "rebuild");
+        drop(guard);
+    }
+
+    #[tokio::test]
+    async fn lock_fails_fast_unless_asked_to_wait() {
+        let locks = AppLocks::default();
+        let guard = locks.lock("demo", "deploy", false).await.unwrap();
+        assert_eq!(locks.lock("demo", "stop", false).await.err().unwrap().operation, "deploy");
+        let waiting = tokio::time::timeout(Duration::from_millis(50), locks.lock("demo", "stop", true)).await;
+        assert!(waiting.is_err());
+        drop(guard);
+    }
+
+    #[tokio::test]
+    async fn lock_pair_holds_both_apps() {
+        let locks = AppLocks::default();
+        let guards = locks.lock_pair("b", "a", "rename", false).await.unwrap();
+        assert_eq!(locks.try_acquire("a", "deploy").err().unwrap().operation, "rename");
+        assert_eq!(locks.try_acquire("b", "deploy").err().unwrap().operation, "rename");
+        assert!(locks.lock_pair("a", "b", "rename", false).await.is_err());
+
+        drop(guards);
+        assert!(locks.try_acquire("a", "deploy").is_ok());
+        assert!(locks.try_acquire("b", "deploy").is_ok());
+    }
+
+    #[tokio::test]
+    async fn opposite_lock_pairs_do_not_deadlock() {
+        let locks = AppLocks::default();
+        let guard = locks.try_acquire("a", "deploy").unwrap();
+        let pair = |a: &'static str, b: &'static str| {
+            let locks = locks.clone();
+            tokio::spawn(async move {
+                let _guards = locks.lock_pair(a, b, "rename", true).await.unwrap();
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            })
+        };
+        let (forward, backward) = (pair("a", "b"), pair("b", "a"));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        drop(guard);
+        let (forward, backward) = tokio::time::timeout(TIMEOUT, async { tokio::join!(forward, backward) }).await.unwrap();
+        forward.unwrap();
+        backward.unwrap();
+    }
+}
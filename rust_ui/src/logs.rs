@@ -0,0 +1,263 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{sse::{Event, Sse}, IntoResponse, Response},
+    Json,
+};
+use regex::Regex;
+use serde::Deserialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::runtime::{LogOptions, LogStream, RuntimeError};
+use crate::AppState;
+
+// ── Container log streaming ──
+//
+// GET /api/logs/{app}/stream sends each log line as an SSE event named after
+// its stream ("stdout" / "stderr"). By default it replays the last 100 lines
+// and then follows new output until the client disconnects; a final "done"
+// event is sent if the log ends first (container stopped, or follow=false).
+// Apps with several replicas stream all of them, each line prefixed with
+// "[{replica}] ", unless `replica` picks one.
+
+const DEFAULT_TAIL: usize = 100;
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+pub struct LogStreamQuery {
+    // Number of lines, or "all"
+    tail: Option<String>,
+    // Unix seconds, RFC 3339 ("2026-01-02T15:04:05Z") or relative ("10m", "2h")
+    since: Option<String>,
+    until: Option<String>,
+    #[serde(default = "default_true")]
+    stdout: bool,
+    #[serde(default = "default_true")]
+    stderr: bool,
+    #[serde(default)]
+    timestamps: bool,
+    #[serde(default = "default_true")]
+    follow: bool,
+    // Keep only lines containing this text
+    filter: Option<String>,
+    // Keep only lines matching this regular expression
+    regex: Option<String>,
+    // Container name of a single replica
+    replica: Option<String>,
+}
+
+struct LineFilter {
+    substring: Option<String>,
+    regex: Option<Regex>,
+    // Docker prefixes lines with a timestamp that should not be matched
+    skip_timestamp: bool,
+}
+
+impl LineFilter {
+    fn matches(&self, line: &str) -> bool {
+        let text = if self.skip_timestamp {
+            line.split_once(' ').map(|(_, rest)| rest).unwrap_or(line)
+        } else {
+            line
+        };
+        self.substring.as_deref().is_none_or(|s| text.contains(s))
+            && self.regex.as_ref().is_none_or(|r| r.is_match(text))
+    }
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+pub fn parse_rfc3339(value: &str) -> Option<i64> {
+    let num = |s: &str| s.parse::<i64>().ok();
+    let (date, time) = value.split_once(['T', ' ']).unwrap_or((value, "00:00:00Z"));
+    let mut d = date.splitn(3, '-');
+    let (year, month, day) = (num(d.next()?)?, num(d.next()?)?, num(d.next()?)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Offset: Z, +hh:mm or -hh:mm (no offset means UTC)
+    let (clock, offset) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else if let Some(pos) = time.rfind(['+', '-']) {
+        let (h, m) = time[pos + 1..].split_once(':')?;
+        let sign = if time[pos..].starts_with('-') { -1 } else { 1 };
+        (&time[..pos], sign * (num(h)? * 3600 + num(m)? * 60))
+    } else {
+        (time, 0)
+    };
+    let clock = clock.split('.').next()?;
+    let mut t = clock.splitn(3, ':');
+    let (h, m, s) = (num(t.next()?)?, num(t.next()?)?, num(t.next().unwrap_or("0"))?);
+    if h > 23 || m > 59 || s > 60 {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86400 + h * 3600 + m * 60 + s - offset)
+}
+
+// Unix timestamp for a `since` / `until` value
+fn parse_time(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(secs) = value.split('.').next()?.parse::<i64>()
+        && value.chars().all(|c| c.is_ascii_digit() || c == '.')
+    {
+        return Some(secs);
+    }
+    let unit = match value.chars().last()? {
+        's' => Some(1),
+        'm' => Some(60),
+        'h' => Some(3600),
+        'd' => Some(86400),
+        _ => None,
+    };
+    if let Some(unit) = unit
+        && let Ok(n) = value[..value.len() - 1].parse::<i64>()
+    {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        return Some(now - n * unit);
+    }
+    parse_rfc3339(value)
+}
+
+fn bad_request(message: String) -> Response {
+    (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": message}))).into_response()
+}
+
+pub async fn stream_logs(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Query(query): Query<LogStreamQuery>,
+) -> Response {
+    let tail = match query.tail.as_deref() {
+        None => Some(DEFAULT_TAIL),
+        Some("all") => None,
+        Some(n) => match n.parse() {
+            Ok(n) => Some(n),
+            Err(_) => return bad_request(format!("tail '{}' must be a number or \"all\"", n)),
+        },
+    };
+    let mut times = [None, None];
+    for (slot, (name, value)) in times.iter_mut().zip([("since", &query.since), ("until", &query.until)]) {
+        if let Some(value) = value {
+            match parse_time(value) {
+                Some(t) => *slot = Some(t),
+                None => return bad_request(format!("{} '{}' is not a timestamp or duration like \"10m\"", name, value)),
+            }
+        }
+    }
+    if !query.stdout && !query.stderr {
+        return bad_request("at least one of stdout and stderr must be enabled".to_string());
+    }
+    let regex = match query.regex.as_deref().map(Regex::new).transpose() {
+        Ok(r) => r,
+        Err(e) => return bad_request(format!("invalid regex: {}", e)),
+    };
+    let filter = LineFilter { substring: query.filter, regex, skip_timestamp: query.timestamps };
+
+    let not_found = |msg: &str| (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": msg}))).into_response();
+    let mut names = state.status.replica_names(&app_name);
+    if let Some(replica) = query.replica {
+        if !names.contains(&replica) {
+            return not_found("Replica not found");
+        }
+        names = vec![replica];
+    }
+    if let [name] = names.as_slice()
+        && let Err(RuntimeError::NotFound(_)) = state.runtime.inspect_container(name).await
+    {
+        return not_found("Container not found");
+    }
+    let options = LogOptions {
+        tail,
+        since: times[0],
+        until: times[1],
+        stdout: query.stdout,
+        stderr: query.stderr,
+        timestamps: query.timestamps,
+        follow: query.follow,
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(256);
+    tokio::spawn(async move {
+        let prefix = names.len() > 1;
+        let (sink, mut lines) = tokio::sync::mpsc::unbounded_channel::<(String, LogStream, String)>();
+        let mut readers = tokio::task::JoinSet::new();
+        for name in names {
+            let (replica_sink, mut replica_lines) = tokio::sync::mpsc::unbounded_channel();
+            let runtime = state.runtime.clone();
+            let options = options.clone();
+            let sink = sink.clone();
+            readers.spawn(async move {
+                let container = name.clone();
+                let read = async move { runtime.stream_logs(&container, &options, &replica_sink).await };
+                let forward = async {
+                    while let Some((stream, line)) = replica_lines.recv().await {
+                        if sink.send((name.clone(), stream, line)).is_err() {
+                            break;
+                        }
+                    }
+                    // Closing the receiver stops a following reader once the client is gone
+                    drop(replica_lines);
+                };
+                tokio::join!(read, forward).0
+            });
+        }
+        drop(sink);
+
+        // A quiet container sends nothing, so notice the client leaving on its own
+        loop {
+            let (name, stream, line) = tokio::select! {
+                next = lines.recv() => match next {
+                    Some(next) => next,
+                    None => break,
+                },
+                _ = tx.closed() => {
+                    readers.abort_all();
+                    return;
+                }
+            };
+            if !filter.matches(&line) {
+                continue;
+            }
+            let line = if prefix { format!("[{}] {}", name, line) } else { line };
+            if tx.send(Ok(Event::default().event(stream.label()).data(line))).await.is_err() {
+                readers.abort_all();
+                return;
+            }
+        }
+
+        let mut done = "end";
+        while let Some(result) = readers.join_next().await {
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    let _ = tx.send(Ok(Event::default().data(format!("✗ Failed to read logs: {}", e)))).await;
+                    done = "failed";
+                }
+                Err(_) => done = "failed",
+            }
+        }
+        let _ = tx.send(Ok(Event::default().event("done").data(done))).await;
+    });
+
+    Sse::new(ReceiverStream::new(rx))
+        .keep_alive(axum::response::sse::KeepAlive::default())
+        .into_response()
+}
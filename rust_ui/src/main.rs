@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{DefaultBodyLimit, Path, Query, State},
     http::{HeaderMap, Request, StatusCode},
     middleware::{self, Next},
     response::{sse::{Event, Sse}, IntoResponse, Response},
@@ -12,16 +12,41 @@ use std::collections::HashMap;
 use std::convert::Infallible;
 use std::process::Command;
 use std::sync::{Arc, RwLock};
-use tokio::io::AsyncBufReadExt;
-use tokio::process::Command as TokioCommand;
 use tokio_stream::wrappers::ReceiverStream;
 use tower_http::services::{ServeDir, ServeFile};
 use std::fs;
 
-#[cfg(unix)]
-use std::os::unix::process::ExitStatusExt;
-#[cfg(windows)]
-use std::os::windows::process::ExitStatusExt;
+mod backup;
+mod builds;
+mod bundles;
+mod clone;
+mod events;
+mod git;
+mod idle;
+mod jobs;
+mod limits;
+mod locks;
+mod logs;
+mod manifest;
+mod readiness;
+mod rename;
+mod replicas;
+mod restarts;
+mod revisions;
+mod rollout;
+mod runtime;
+mod secrets;
+mod stats;
+mod templates;
+mod terminal;
+mod traefik;
+mod volumes;
+
+use jobs::JobError;
+use locks::{LockConflict, WaitQuery};
+use manifest::{AppManifest, DeployStrategy, Resources};
+use runtime::{BuildSpec, ContainerRuntime, LogSink, RunSpec};
+use traefik::{ExtraRoute, RouteSpec};
 
 // ── Data structures ──
 
@@ -30,6 +55,12 @@ struct AppStatus {
     name: String,
     status: String,
     auth_type: String,
+    resources: Resources,
+    crash_loop: bool,
+    restart_count: i64,
+    // Latest usage sample while the app is running (summed over replicas)
+    stats: Option<stats::StatsPoint>,
+    replicas: Vec<ReplicaStatus>,
 }
 
 #[derive(Deserialize)]
@@ -61,6 +92,9 @@ struct Group {
     name: String,
     description: String,
     containers: Vec<String>,
+    // Default resource limits for member apps
+    #[serde(default)]
+    resources: Resources,
     created_at: String,
     updated_at: String,
 }
@@ -71,6 +105,8 @@ struct ContainerSummary {
     running: usize,
     stopped: usize,
     error: usize,
+    #[serde(flatten)]
+    usage: stats::UsageTotals,
 }
 
 #[derive(Serialize)]
@@ -81,6 +117,7 @@ struct GroupResponse {
     description: String,
     containers: Vec<String>,
     container_summary: ContainerSummary,
+    resources: Resources,
     created_at: String,
     updated_at: String,
 }
@@ -89,12 +126,16 @@ struct GroupResponse {
 struct CreateGroupRequest {
     name: String,
     description: String,
+    #[serde(default)]
+    resources: Resources,
 }
 
 #[derive(Deserialize)]
 struct UpdateGroupRequest {
     name: String,
     description: String,
+    // Left unchanged when omitted
+    resources: Option<Resources>,
 }
 
 #[derive(Deserialize)]
@@ -102,6 +143,17 @@ struct AddContainerRequest {
     container_name: String,
 }
 
+#[derive(Deserialize)]
+struct CreateQuery {
+    // Clone this repository instead of scaffolding from a template
+    git_url: Option<String>,
+    branch: Option<String>,
+    template: Option<String>,
+    // Any other parameter is a template variable
+    #[serde(flatten)]
+    variables: HashMap<String, String>,
+}
+
 #[derive(Deserialize)]
 struct UpdateDockerfileRequest {
     content: String,
@@ -115,6 +167,22 @@ struct AppState {
     jwks_cache: Arc<RwLock<Option<JwksCache>>>,
     manager_ip: String,
     groups: Arc<RwLock<Vec<Group>>>,
+    runtime: Arc<dyn ContainerRuntime>,
+    jobs: jobs::JobManager,
+    locks: locks::AppLocks,
+    routes_config: Arc<RwLock<HashMap<String, Vec<ExtraRoute>>>>,
+    secrets: secrets::SecretStore,
+    resources_config: Arc<RwLock<HashMap<String, Resources>>>,
+    ceiling: limits::ResourceCeiling,
+    readiness_timeout: std::time::Duration,
+    exec_idle_timeout: std::time::Duration,
+    crash_watch: restarts::CrashWatcher,
+    stats: stats::StatsCollector,
+    // Container states kept current from Docker events
+    status: events::StatusCache,
+    idle: idle::IdleManager,
+    revisions: revisions::RevisionStore,
+    builds: builds::BuildStore,
 }
 
 // ── Auth config persistence ──
@@ -125,6 +193,8 @@ const CODE_SERVER_CONFIG_DIR: &str = "/root/.config/code-server";
 const CODE_SERVER_EXTENSIONS_DIR: &str = "/root/.local/share/code-server/extensions";
 const OFFLINE_VSIX_DIR: &str = "/offline-vsix";
 const GROUPS_CONFIG_PATH: &str = "/apps/groups_config.json";
+const ROUTES_CONFIG_PATH: &str = "/apps/routes_config.json";
+const RESOURCES_CONFIG_PATH: &str = "/apps/resources_config.json";
 
 fn load_groups() -> Vec<Group> {
     match fs::read_to_string(GROUPS_CONFIG_PATH) {
@@ -133,9 +203,9 @@ fn load_groups() -> Vec<Group> {
     }
 }
 
-fn save_groups(groups: &Vec<Group>) -> Result<(), std::io::Error> {
+fn save_groups(groups: &[Group]) -> Result<(), std::io::Error> {
     let json = serde_json::to_string_pretty(groups)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        .map_err(std::io::Error::other)?;
     fs::write(GROUPS_CONFIG_PATH, json)
 }
 
@@ -167,37 +237,97 @@ fn now_iso8601() -> String {
 }
 
 fn is_leap_year(year: u32) -> bool {
-    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
 }
 
-fn empty_process_output() -> std::process::Output {
-    std::process::Output {
-        status: std::process::ExitStatus::from_raw(0),
-        stdout: Vec::new(),
-        stderr: Vec::new(),
+// App directories under /apps (each one is an app and its container name)
+fn app_dir_names() -> Vec<String> {
+    let Ok(paths) = fs::read_dir("/apps") else {
+        return vec![];
+    };
+    let mut names = vec![];
+    for entry in paths.flatten() {
+        if let Ok(ft) = entry.file_type()
+            && ft.is_dir()
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with('.') && name != "auth_config.json" && name != "groups_config.json" {
+                names.push(name);
+            }
+        }
     }
+    names
+}
+
+// Names usable as a directory, container, DNS alias and Traefik router name
+fn valid_app_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_lowercase() || c.is_ascii_digit())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && name.len() <= 63
 }
 
-fn get_running_containers() -> HashMap<String, String> {
-    let docker_ps = Command::new("docker")
-        .args(["ps", "--format", "{{.Names}}|{{.Status}}"])
-        .output()
-        .unwrap_or_else(|_| empty_process_output());
-    let ps_output = String::from_utf8_lossy(&docker_ps.stdout);
-    let mut running = HashMap::new();
-    for line in ps_output.lines() {
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.len() == 2 {
-            running.insert(parts[0].to_string(), parts[1].to_string());
+#[derive(Serialize)]
+struct ReplicaStatus {
+    name: String,
+    status: String,
+    running: bool,
+    crash_loop: bool,
+    restart_count: i64,
+}
+
+// Status shown for an app and each of its replicas: crash state, then
+// `docker ps`-style uptime. With several replicas the app shows the first
+// running one's uptime and how many are up, e.g. "Up 5 minutes (2/3 replicas)".
+fn app_status(state: &AppState, app_name: &str) -> (String, Vec<ReplicaStatus>) {
+    let replicas: Vec<ReplicaStatus> = state.status.replicas(app_name)
+        .into_iter()
+        .map(|r| {
+            let crash = state.crash_watch.status(&r.name);
+            ReplicaStatus {
+                status: crash.as_ref().and_then(|c| c.label()).unwrap_or(r.status),
+                running: r.running,
+                crash_loop: crash.as_ref().is_some_and(|c| c.crash_loop),
+                restart_count: crash.map(|c| c.restart_count).unwrap_or(0),
+                name: r.name,
+            }
+        })
+        .collect();
+    let label = match replicas.as_slice() {
+        [] if state.idle.is_sleeping(app_name) => "Sleeping".to_string(),
+        [] => "Not Started".to_string(),
+        [only] => only.status.clone(),
+        all => {
+            let up = all.iter().filter(|r| r.running && !r.crash_loop).count();
+            match all.iter().find(|r| r.running && !r.crash_loop) {
+                Some(first) => format!("{} ({}/{} replicas)", first.status, up, all.len()),
+                None => all[0].status.clone(),
+            }
         }
-    }
-    running
+    };
+    (label, replicas)
 }
 
-fn compute_summary(containers: &[String], running: &HashMap<String, String>) -> ContainerSummary {
+fn compute_summary(state: &AppState, containers: &[String]) -> ContainerSummary {
     let total = containers.len();
-    let running_count = containers.iter().filter(|name| running.contains_key(*name)).count();
-    ContainerSummary { total, running: running_count, stopped: total - running_count, error: 0 }
+    let mut running = 0;
+    let mut error = 0;
+    for app_name in containers {
+        let replicas = state.status.replicas(app_name);
+        // Crash-looping or failed replicas count as errors, not running/stopped
+        if replicas.iter().any(|r| state.crash_watch.status(&r.name).is_some_and(|c| c.is_error())) {
+            error += 1;
+        } else if replicas.iter().any(|r| r.running) {
+            running += 1;
+        }
+    }
+    ContainerSummary {
+        total,
+        running,
+        stopped: total - running - error,
+        error,
+        usage: state.stats.totals(containers),
+    }
 }
 
 fn load_auth_config() -> HashMap<String, AuthAppConfig> {
@@ -209,10 +339,35 @@ fn load_auth_config() -> HashMap<String, AuthAppConfig> {
 
 fn save_auth_config(config: &HashMap<String, AuthAppConfig>) -> Result<(), std::io::Error> {
     let json = serde_json::to_string_pretty(config)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        .map_err(std::io::Error::other)?;
     fs::write(AUTH_CONFIG_PATH, json)
 }
 
+fn load_routes_config() -> HashMap<String, Vec<ExtraRoute>> {
+    match fs::read_to_string(ROUTES_CONFIG_PATH) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn save_routes_config(config: &HashMap<String, Vec<ExtraRoute>>) -> Result<(), std::io::Error> {
+    let json = serde_json::to_string_pretty(config)
+        .map_err(std::io::Error::other)?;
+    fs::write(ROUTES_CONFIG_PATH, json)
+}
+
+fn load_resources_config() -> HashMap<String, Resources> {
+    fs::read_to_string(RESOURCES_CONFIG_PATH)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_resources_config(config: &HashMap<String, Resources>) -> Result<(), std::io::Error> {
+    let json = serde_json::to_string_pretty(config).map_err(std::io::Error::other)?;
+    fs::write(RESOURCES_CONFIG_PATH, json)
+}
+
 fn code_server_config_dir(app_name: &str) -> String {
     format!("{}/{}", CODE_SERVER_HOST_ROOT, app_name)
 }
@@ -228,20 +383,102 @@ fn ensure_code_server_host_dirs(app_name: &str) {
     let _ = fs::create_dir_all(&extensions_dir);
 }
 
-fn add_app_runtime_mounts(args: &mut Vec<String>, app_name: &str) {
-    args.push("-v".into());
-    args.push(format!("/apps/{}:/app", app_name));
-    args.push("-v".into());
-    args.push(format!("{}:{}", code_server_config_dir(app_name), CODE_SERVER_CONFIG_DIR));
-    args.push("-v".into());
-    args.push(format!("{}:{}", code_server_extensions_host_dir(app_name), CODE_SERVER_EXTENSIONS_DIR));
-    args.push("-v".into());
-    args.push(format!("{}:/opt/offline-vsix:ro", OFFLINE_VSIX_DIR));
+fn add_app_runtime_mounts(spec: &mut RunSpec, app_name: &str, manifest: &AppManifest) {
+    spec.binds.push(format!("/apps/{}:/app", app_name));
+    spec.binds.push(format!("{}:{}", code_server_config_dir(app_name), CODE_SERVER_CONFIG_DIR));
+    spec.binds.push(format!("{}:{}", code_server_extensions_host_dir(app_name), CODE_SERVER_EXTENSIONS_DIR));
+    spec.binds.push(format!("{}:/opt/offline-vsix:ro", OFFLINE_VSIX_DIR));
+    spec.binds.extend(volumes::binds(app_name, manifest));
+}
+
+// Image build settings shared by deploy and rebuild
+fn app_build_spec(app_name: &str) -> BuildSpec {
+    BuildSpec {
+        tag: app_name.to_string(),
+        context_dir: format!("/apps/{}", app_name),
+        network_mode: Some("host".into()),
+    }
+}
+
+// Manifest routes merged with hub-side routes (hub entries win on name clashes)
+fn effective_routes(state: &AppState, app_name: &str, manifest: &AppManifest) -> Vec<ExtraRoute> {
+    let hub = state.routes_config.read().unwrap()
+        .get(app_name)
+        .cloned()
+        .unwrap_or_default();
+    let mut routes: Vec<ExtraRoute> = manifest.routes.iter()
+        .filter(|r| !hub.iter().any(|h| h.name == r.name))
+        .cloned()
+        .collect();
+    routes.extend(hub);
+    routes
+}
+
+fn app_route_spec(state: &AppState, app_name: &str, manifest: &AppManifest) -> RouteSpec {
+    RouteSpec::for_app(app_name, &state.manager_ip)
+        .with_ports(manifest.ports.api, manifest.ports.ide)
+        .with_extra_routes(&effective_routes(state, app_name, manifest))
+}
+
+// Container spec shared by deploy and rebuild: network, env, mounts, limits and Traefik labels.
+// Stored env vars/secrets come after manifest env so they take precedence.
+fn app_run_spec(state: &AppState, app_name: &str, manifest: &AppManifest) -> Result<RunSpec, String> {
+    let mut spec = RunSpec::new(app_name, app_name);
+    spec.network = Some(manifest.network.clone());
+    spec.env.push(format!("APP_NAME={}", app_name));
+    spec.env.extend(manifest.env.iter().map(|(k, v)| format!("{}={}", k, v)));
+    add_app_runtime_mounts(&mut spec, app_name, manifest);
+    spec.env.extend(state.secrets.env_for(app_name)?);
+    spec.labels.insert(restarts::APP_LABEL.to_string(), app_name.to_string());
+    spec.restart = Some(restarts::restart_spec(manifest.restart));
+
+    let resources = limits::effective(state, app_name, manifest)?;
+    spec.memory_bytes = resources.memory.as_deref().and_then(manifest::parse_memory);
+    spec.nano_cpus = resources.cpus.map(|c| (c * 1e9) as i64);
+    spec.pids_limit = resources.pids;
+    spec.healthcheck = manifest.healthcheck.as_ref().map(|hc| runtime::HealthcheckSpec {
+        test: vec![
+            "CMD-SHELL".into(),
+            format!("curl -fsS -o /dev/null http://localhost:{}{} || exit 1", manifest.ports.api, hc.path),
+        ],
+        interval_secs: hc.interval_secs,
+        timeout_secs: hc.timeout_secs,
+        retries: hc.retries,
+    });
+
+    app_route_spec(state, app_name, manifest).apply(&mut spec);
+    Ok(spec)
+}
+
+// Seeds the app's auth config from the manifest's [auth] table if the hub has none yet
+fn apply_default_auth(state: &AppState, app_name: &str, manifest: &AppManifest) {
+    let Some(default) = &manifest.auth else { return };
+    let mut config = state.auth_config.write().unwrap();
+    if config.contains_key(app_name) {
+        return;
+    }
+    let auth = match default {
+        manifest::DefaultAuth::None => AuthAppConfig::None,
+        manifest::DefaultAuth::ApiKey { api_key } => AuthAppConfig::ApiKey {
+            api_key: api_key.clone().unwrap_or_else(|| format!("{:032x}", rand::random::<u128>())),
+        },
+        manifest::DefaultAuth::EntraId { tenant_id, client_id } => AuthAppConfig::EntraId {
+            tenant_id: tenant_id.clone(),
+            client_id: client_id.clone(),
+        },
+    };
+    config.insert(app_name.to_string(), auth);
+    let _ = save_auth_config(&config);
+}
+
+fn manifest_error_text(errors: &[manifest::ManifestError]) -> String {
+    errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
 }
 
-fn code_server_start_command(app_name: &str) -> String {
+fn code_server_start_command(app_name: &str, ide_port: u16) -> String {
     format!(
-        "code-server --auth password --bind-addr 0.0.0.0:8000 --abs-proxy-base-path /{}-ide --extensions-dir {} /app",
+        "code-server --auth password --bind-addr 0.0.0.0:{} --abs-proxy-base-path /{}-ide --extensions-dir {} /app",
+        ide_port,
         app_name,
         CODE_SERVER_EXTENSIONS_DIR
     )
@@ -291,6 +528,22 @@ async fn main() {
     let groups = load_groups();
     println!("Loaded {} groups", groups.len());
 
+    let routes_config = load_routes_config();
+    // Starting with an empty store would overwrite the file on the next change
+    let secrets = match secrets::SecretStore::from_env(&manager_password) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("{}; fix or remove it to start", e);
+            std::process::exit(1);
+        }
+    };
+    let resources_config = load_resources_config();
+
+    let max_concurrent_jobs = std::env::var("MAX_CONCURRENT_JOBS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(2);
+
     let state = Arc::new(AppState {
         session_secret,
         manager_password,
@@ -298,7 +551,27 @@ async fn main() {
         jwks_cache: Arc::new(RwLock::new(None)),
         manager_ip,
         groups: Arc::new(RwLock::new(groups)),
+        runtime: runtime::runtime_from_env(),
+        jobs: jobs::JobManager::new(max_concurrent_jobs),
+        locks: locks::AppLocks::default(),
+        routes_config: Arc::new(RwLock::new(routes_config)),
+        secrets,
+        resources_config: Arc::new(RwLock::new(resources_config)),
+        ceiling: limits::ResourceCeiling::from_env(),
+        readiness_timeout: readiness::timeout_from_env(),
+        exec_idle_timeout: terminal::idle_timeout_from_env(),
+        crash_watch: restarts::CrashWatcher::from_env(),
+        stats: stats::StatsCollector::from_env(),
+        status: events::StatusCache::new(),
+        idle: idle::IdleManager::from_env(),
+        revisions: revisions::RevisionStore::from_env(),
+        builds: builds::BuildStore::from_env(),
     });
+    state.crash_watch.spawn(state.runtime.clone());
+    state.stats.spawn(state.runtime.clone());
+    state.status.sync(state.runtime.as_ref()).await;
+    state.status.spawn(state.runtime.clone());
+    state.idle.spawn(state.clone());
 
     let serve_dir = ServeDir::new("frontend/out")
         .not_found_service(ServeFile::new("frontend/out/index.html"));
@@ -309,16 +582,45 @@ async fn main() {
         .route("/apps/{app_name}/auth", get(get_auth_config).post(set_auth_config))
         .route("/deploy/{app_name}", post(deploy_app))
         .route("/logs/{app_name}", get(get_logs))
+        .route("/logs/{app_name}/stream", get(logs::stream_logs))
         .route("/stop/{app_name}", post(stop_app))
         .route("/delete/{app_name}", post(delete_app))
         .route("/password/{app_name}", get(get_password))
         .route("/password/{app_name}/reset", post(reset_password))
         .route("/create/{app_name}", post(create_app))
+        .route("/templates", get(templates::list_templates))
         .route("/groups", get(list_groups).post(create_group))
         .route("/groups/{id}", put(update_group).delete(delete_group))
         .route("/groups/{id}/containers", post(add_container_to_group))
         .route("/groups/{id}/containers/{container}", delete(remove_container_from_group))
         .route("/apps/{app_name}/dockerfile", get(get_dockerfile).put(update_dockerfile))
+        .route("/apps/{app_name}/routes", get(get_routes).put(set_routes))
+        .route("/apps/{app_name}/manifest", get(get_manifest))
+        .route("/apps/{app_name}/resources", get(limits::get_resources).put(limits::set_resources))
+        .route("/apps/{app_name}/env", get(secrets::list_env))
+        .route("/apps/{app_name}/env/{name}", put(secrets::set_env).delete(secrets::delete_env))
+        .route("/apps/{app_name}/revisions", get(revisions::list_revisions))
+        .route("/apps/{app_name}/rollback/{rev}", post(revisions::rollback))
+        .route("/apps/{app_name}/builds", get(builds::list_builds))
+        .route("/apps/{app_name}/builds/{id}", get(builds::get_build))
+        .route("/apps/{app_name}/builds/{id}/log", get(builds::stream_build_log))
+        .route("/apps/{app_name}/exec", get(terminal::exec_shell))
+        .route("/apps/{app_name}/stats", get(stats::get_stats))
+        .route("/apps/{app_name}/backup", get(backup::backup_app))
+        .route("/apps/restore", post(backup::restore_app).layer(DefaultBodyLimit::max(backup::restore_max_bytes())))
+        .route("/apps/{app_name}/clone", post(clone::clone_app))
+        .route("/apps/{app_name}/rename", post(rename::rename_app))
+        .route("/apps/{app_name}/git", get(git::get_git))
+        .route("/apps/{app_name}/pull", post(git::pull))
+        .route("/apps/{app_name}/export", get(bundles::export_app))
+        .route("/apps/import", post(bundles::import_app).layer(DefaultBodyLimit::max(backup::restore_max_bytes())))
+        .route("/apps/{app_name}/volumes", get(volumes::list_volumes))
+        .route("/apps/{app_name}/volumes/{name}", get(volumes::inspect_volume))
+        .route("/apps/{app_name}/volumes/{name}/size", get(volumes::volume_size))
+        .route("/apps/{app_name}/exec/audit", get(terminal::list_exec_audit))
+        .route("/events", get(events::stream_events))
+        .route("/jobs", get(jobs::list_jobs))
+        .route("/jobs/{id}", get(jobs::get_job).delete(jobs::cancel_job))
         .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
 
     // Public API routes (no dashboard auth)
@@ -345,7 +647,10 @@ async fn main() {
     let bind_addr = format!("0.0.0.0:{}", port);
     println!("Manager UI running on http://{}", bind_addr);
     let listener = tokio::net::TcpListener::bind(&bind_addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // Peer addresses are recorded in the exec audit log
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .await
+        .unwrap();
 }
 
 // ── Dashboard login/logout/check ──
@@ -453,6 +758,92 @@ async fn update_dockerfile(
     }
 }
 
+// ── Extra Traefik routes ──
+
+async fn get_routes(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> Json<serde_json::Value> {
+    let routes = state.routes_config.read().unwrap()
+        .get(&app_name)
+        .cloned()
+        .unwrap_or_default();
+    let manifest = manifest::load(&app_name).unwrap_or_default();
+    let labels = app_route_spec(&state, &app_name, &manifest).labels();
+    Json(serde_json::json!({ "routes": routes, "labels": labels }))
+}
+
+// ── App manifest ──
+
+async fn get_manifest(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> Response {
+    if app_name.contains('/') || app_name.contains("..") {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "Invalid app name"}))).into_response();
+    }
+    if !std::path::Path::new(&format!("/apps/{}", app_name)).exists() {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "App not found"}))).into_response();
+    }
+    let mut manifest = match manifest::load(&app_name) {
+        Ok(m) => m,
+        Err(errors) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(serde_json::json!({"error": "Invalid mcphub.toml", "errors": errors})),
+            )
+                .into_response();
+        }
+    };
+
+    // Merge hub-side settings the same way deploy does
+    manifest.routes = effective_routes(&state, &app_name, &manifest);
+    let auth_type = {
+        let config = state.auth_config.read().unwrap();
+        match config.get(&app_name) {
+            Some(AuthAppConfig::None) => Some(("none", "hub")),
+            Some(AuthAppConfig::ApiKey { .. }) => Some(("api_key", "hub")),
+            Some(AuthAppConfig::EntraId { .. }) => Some(("entra_id", "hub")),
+            None => None,
+        }
+    };
+    let (auth_type, auth_source) = auth_type.unwrap_or(match &manifest.auth {
+        Some(manifest::DefaultAuth::None) => ("none", "manifest"),
+        Some(manifest::DefaultAuth::ApiKey { .. }) => ("api_key", "manifest"),
+        Some(manifest::DefaultAuth::EntraId { .. }) => ("entra_id", "manifest"),
+        None => ("none", "default"),
+    });
+    let mut effective = serde_json::to_value(&manifest).unwrap_or_default();
+    effective["auth"] = serde_json::json!({ "auth_type": auth_type, "source": auth_source });
+    effective["env"]["APP_NAME"] = serde_json::json!(app_name);
+    for (name, value) in state.secrets.masked(&app_name) {
+        effective["env"][name] = serde_json::json!(value);
+    }
+
+    Json(effective).into_response()
+}
+
+async fn set_routes(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Json(routes): Json<Vec<ExtraRoute>>,
+) -> Json<serde_json::Value> {
+    if let Err(e) = traefik::validate_routes(&routes) {
+        return Json(serde_json::json!({"error": e}));
+    }
+    let mut config = state.routes_config.write().unwrap();
+    if routes.is_empty() {
+        config.remove(&app_name);
+    } else {
+        config.insert(app_name, routes);
+    }
+    // Labels are fixed at container creation, so changes apply on the next deploy/rebuild
+    match save_routes_config(&config) {
+        Ok(_) => Json(serde_json::json!({"status": "ok"})),
+        Err(e) => Json(serde_json::json!({"error": format!("Failed to save: {}", e)})),
+    }
+}
+
 // ── ForwardAuth verify endpoint (called by Traefik) ──
 
 async fn verify_forward_auth(
@@ -475,6 +866,16 @@ async fn verify_forward_auth(
         return StatusCode::OK.into_response();
     }
 
+    let resp = check_app_auth(&state, &headers, app_name).await;
+    if resp.status().is_success()
+        && let Err(unavailable) = idle::on_request(&state, app_name).await
+    {
+        return unavailable;
+    }
+    resp
+}
+
+async fn check_app_auth(state: &Arc<AppState>, headers: &HeaderMap, app_name: &str) -> Response {
     // Look up auth config for this app
     let auth = {
         let config = state.auth_config.read().unwrap();
@@ -501,7 +902,7 @@ async fn verify_forward_auth(
             }
         }
         AuthAppConfig::EntraId { tenant_id, client_id } => {
-            match validate_entra_token(&state, &headers, &tenant_id, &client_id).await {
+            match validate_entra_token(state, headers, &tenant_id, &client_id).await {
                 Ok(user) => {
                     let mut resp = StatusCode::OK.into_response();
                     resp.headers_mut().insert(
@@ -581,12 +982,11 @@ async fn get_jwks(
     // Check cache
     {
         let cache = state.jwks_cache.read().unwrap();
-        if let Some(ref cached) = *cache {
-            if cached.tenant_id == tenant_id
-                && cached.fetched_at.elapsed().as_secs() < JWKS_CACHE_SECS
-            {
-                return Ok(cached.keys.clone());
-            }
+        if let Some(ref cached) = *cache
+            && cached.tenant_id == tenant_id
+            && cached.fetched_at.elapsed().as_secs() < JWKS_CACHE_SECS
+        {
+            return Ok(cached.keys.clone());
         }
     }
     // Fetch fresh
@@ -634,47 +1034,40 @@ async fn list_apps(State(state): State<Arc<AppState>>) -> Json<Vec<AppStatus>> {
         Err(_) => return Json(apps),
     };
 
-    let docker_ps = Command::new("docker")
-        .args(["ps", "--format", "{{.Names}}|{{.Status}}"])
-        .output()
-        .unwrap_or_else(|_| empty_process_output());
-
-    let ps_output = String::from_utf8_lossy(&docker_ps.stdout);
-    let mut running_containers = std::collections::HashMap::new();
-
-    for line in ps_output.lines() {
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.len() == 2 {
-            running_containers.insert(parts[0].to_string(), parts[1].to_string());
-        }
-    }
-
     let auth_config = state.auth_config.read().unwrap();
 
-    for path in paths {
-        if let Ok(entry) = path {
-            if let Ok(file_type) = entry.file_type() {
-                if file_type.is_dir() {
-                    let name = entry.file_name().to_string_lossy().to_string();
-                    if name == "auth_config.json" || name.starts_with('.') {
-                        continue;
-                    }
-                    let status = running_containers
-                        .get(&name)
-                        .cloned()
-                        .unwrap_or_else(|| "Not Started".to_string());
-                    let auth_type = auth_config.get(&name)
-                        .map(|a| match a {
-                            AuthAppConfig::None => "none",
-                            AuthAppConfig::ApiKey { .. } => "api_key",
-                            AuthAppConfig::EntraId { .. } => "entra_id",
-                        })
-                        .unwrap_or("none")
-                        .to_string();
-
-                    apps.push(AppStatus { name, status, auth_type });
-                }
+    for entry in paths.flatten() {
+        if let Ok(file_type) = entry.file_type()
+            && file_type.is_dir()
+        {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == "auth_config.json" || name.starts_with('.') {
+                continue;
             }
+            let (status, replicas) = app_status(&state, &name);
+            let auth_type = auth_config.get(&name)
+                .map(|a| match a {
+                    AuthAppConfig::None => "none",
+                    AuthAppConfig::ApiKey { .. } => "api_key",
+                    AuthAppConfig::EntraId { .. } => "entra_id",
+                })
+                .unwrap_or("none")
+                .to_string();
+
+            let manifest = manifest::load(&name).unwrap_or_default();
+            let resources = limits::effective(&state, &name, &manifest).unwrap_or_default();
+            let stats = state.stats.current(&name);
+
+            apps.push(AppStatus {
+                name,
+                status,
+                auth_type,
+                resources,
+                crash_loop: replicas.iter().any(|r| r.crash_loop),
+                restart_count: replicas.iter().map(|r| r.restart_count).sum(),
+                stats,
+                replicas,
+            });
         }
     }
 
@@ -684,117 +1077,134 @@ async fn list_apps(State(state): State<Arc<AppState>>) -> Json<Vec<AppStatus>> {
 async fn deploy_app(
     State(state): State<Arc<AppState>>,
     Path(app_name): Path<String>,
-) -> Json<serde_json::Value> {
+    Query(query): Query<WaitQuery>,
+    Query(options): Query<rollout::DeployOptions>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, LockConflict> {
     let app_dir = format!("/apps/{}", app_name);
 
     if !std::path::Path::new(&app_dir).exists() {
-        return Json(serde_json::json!({"error": "App directory not found"}));
+        return Ok(Json(serde_json::json!({"error": "App directory not found"})));
     }
 
-    // Stop and remove existing container if any
-    let _ = Command::new("docker")
-        .args(["rm", "-f", &app_name])
-        .output();
+    let strategy = options.strategy
+        .or_else(|| manifest::load(&app_name).ok().map(|m| m.deploy_strategy))
+        .unwrap_or_default();
+    let kind = rollout::job_kind(strategy, "deploy");
+
+    // Fail fast on a busy app unless the caller asked to queue behind it
+    let guard = if query.wait {
+        None
+    } else {
+        Some(state.locks.try_acquire(&app_name, "deploy")?)
+    };
 
-    // Build image
-    let build_status = Command::new("docker")
-        .env("DOCKER_BUILDKIT", "0")
-        .args(["build", "--network", "host", "-t", &app_name, &app_dir])
-        .status()
-        .expect("Failed to execute docker build");
+    // Build and run happen in a background job; the client polls /api/jobs/{id}
+    let trigger = builds::trigger_from_headers(&headers, builds::Trigger::Deploy);
+    let job_state = state.clone();
+    let job_app = app_name.clone();
+    let job_id = state.jobs.submit(&app_name, &kind, move |log| async move {
+        let _guard = match guard {
+            Some(g) => g,
+            None => {
+                let _ = log.send(format!("Waiting for other operations on {} to finish...", job_app));
+                job_state.locks.acquire(&job_app, "deploy").await
+            }
+        };
+        let build = job_state.builds.start(&job_app, trigger);
+        let result = run_deploy(job_state.clone(), job_app, strategy, &build, build.tee(log)).await;
+        build.finish(result.as_ref().map(|_| ()).map_err(|e| e.message.clone())).await;
+        result
+    });
 
-    if !build_status.success() {
-        return Json(serde_json::json!({"error": "Docker build failed"}));
-    }
+    Ok(Json(serde_json::json!({"status": "queued", "job_id": job_id})))
+}
+
+async fn run_deploy(
+    state: Arc<AppState>,
+    app_name: String,
+    strategy: DeployStrategy,
+    build: &builds::BuildRecorder,
+    log: LogSink,
+) -> Result<Option<serde_json::Value>, JobError> {
+    let _ = log.send(format!("Build {}", build.id()));
+
+    // Validate mcphub.toml before touching the running container
+    let manifest = manifest::load(&app_name).map_err(|errors| manifest_error_text(&errors))?;
+    let run_spec = app_run_spec(&state, &app_name, &manifest)?;
+
+    // Build image (the current container keeps running until the new one is started)
+    let image_id = match state.runtime.build_image(&app_build_spec(&app_name), &log).await {
+        Ok(id) => id,
+        Err(e) => return Err(format!("Docker build failed: {}", e).into()),
+    };
+    let _ = log.send("✓ Build successful. Starting container...".to_string());
+    build.set_image(&image_id);
+    revisions::record_build(&state, &app_name, &image_id, "deploy", &log).await;
 
     // Ensure code-server config and extensions directories exist on host for persistence
     ensure_code_server_host_dirs(&app_name);
 
-    // Build docker run args with Traefik labels
-    let mut args: Vec<String> = vec![
-        "run".into(), "-d".into(),
-        "--name".into(), app_name.clone(),
-        "--network".into(), "mcp-net".into(),
-        "-e".into(), format!("APP_NAME={}", app_name),
-    ];
-    add_app_runtime_mounts(&mut args, &app_name);
-
-    // Traefik enable
-    args.push("--label=traefik.enable=true".into());
-
-    // API router
-    args.push(format!("--label=traefik.http.routers.{}.rule=PathPrefix(`/{}`)", app_name, app_name));
-    args.push(format!("--label=traefik.http.routers.{}.service={}", app_name, app_name));
-    args.push(format!("--label=traefik.http.middlewares.{}-strip.stripprefix.prefixes=/{}/, /{}", app_name, app_name, app_name));
-    args.push(format!("--label=traefik.http.services.{}.loadbalancer.server.port=80", app_name));
-
-    // ForwardAuth middleware (always attached — verify endpoint handles "none" as passthrough)
-    args.push(format!(
-        "--label=traefik.http.middlewares.{}-auth.forwardauth.address=http://{}:8081/api/verify",
-        app_name, state.manager_ip
-    ));
-    args.push(format!(
-        "--label=traefik.http.middlewares.{}-auth.forwardauth.authRequestHeaders=X-API-Key,Authorization",
-        app_name
-    ));
-    args.push(format!(
-        "--label=traefik.http.middlewares.{}-auth.forwardauth.authResponseHeaders=X-Forwarded-User",
-        app_name
-    ));
-    // Chain: strip prefix then auth
-    args.push(format!(
-        "--label=traefik.http.routers.{}.middlewares={}-auth,{}-strip",
-        app_name, app_name, app_name
-    ));
-
-    // IDE router (no ForwardAuth — IDE has its own password)
-    args.push(format!("--label=traefik.http.routers.{}-ide.rule=PathPrefix(`/{}-ide`)", app_name, app_name));
-    args.push(format!("--label=traefik.http.routers.{}-ide.service={}-ide", app_name, app_name));
-    args.push(format!("--label=traefik.http.middlewares.{}-ide-strip.stripprefix.prefixes=/{}-ide", app_name, app_name));
-    args.push(format!("--label=traefik.http.routers.{}-ide.middlewares={}-ide-strip", app_name, app_name));
-    args.push(format!("--label=traefik.http.services.{}-ide.loadbalancer.server.port=8000", app_name));
-
-    // Image name
-    args.push(app_name.clone());
-
-    let run_status = Command::new("docker")
-        .args(&args)
-        .status()
-        .expect("Failed to execute docker run");
-
-    if run_status.success() {
-        Json(serde_json::json!({"status": "success"}))
+    apply_default_auth(&state, &app_name, &manifest);
+
+    // Only report success once the app actually serves
+    let readiness = rollout::roll_out(
+        state.runtime.as_ref(), &app_name, &manifest, run_spec, strategy, state.readiness_timeout, &log, &log,
+    ).await?;
+    if let readiness::Readiness::Crashed { logs, .. } = &readiness {
+        for line in logs {
+            let _ = log.send(format!("  | {}", line));
+        }
+    }
+    let result = serde_json::to_value(&readiness).ok();
+    if readiness.is_ready() {
+        let _ = log.send(format!("✓ {}", readiness.summary()));
+        Ok(result)
     } else {
-        Json(serde_json::json!({"error": "Docker run failed"}))
+        Err(JobError { message: readiness.summary(), result })
     }
 }
 
-async fn stop_app(Path(app_name): Path<String>) -> Json<serde_json::Value> {
-    let status = Command::new("docker")
-        .args(["rm", "-f", &app_name])
-        .status();
-
-    match status {
-        Ok(s) if s.success() => Json(serde_json::json!({"status": "success"})),
-        _ => Json(serde_json::json!({"error": "Failed to stop container"}))
+async fn stop_app(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Query(query): Query<WaitQuery>,
+) -> Result<Json<serde_json::Value>, LockConflict> {
+    let _guard = state.locks.lock(&app_name, "stop", query.wait).await?;
+    state.idle.forget(&state, &app_name);
+    match replicas::remove_all(state.runtime.as_ref(), &app_name).await {
+        Ok(_) => Ok(Json(serde_json::json!({"status": "success"}))),
+        Err(e) => Ok(Json(serde_json::json!({"error": format!("Failed to stop container: {}", e)}))),
     }
 }
 
 async fn delete_app(
     State(state): State<Arc<AppState>>,
     Path(app_name): Path<String>,
-) -> Json<serde_json::Value> {
+    Query(query): Query<WaitQuery>,
+    Query(delete): Query<volumes::DeleteQuery>,
+) -> Result<Json<serde_json::Value>, LockConflict> {
+    let _guard = state.locks.lock(&app_name, "delete", query.wait).await?;
+
     // Stop and remove container
-    let _ = Command::new("docker")
-        .args(["rm", "-f", &app_name])
-        .status();
+    state.idle.forget(&state, &app_name);
+    let _ = replicas::remove_all(state.runtime.as_ref(), &app_name).await;
+
+    // Data volumes are kept unless purging was asked for
+    let purged = match delete.volumes {
+        volumes::Retention::Keep => Vec::new(),
+        volumes::Retention::Purge => match volumes::purge(state.runtime.as_ref(), &app_name).await {
+            Ok(removed) => removed,
+            Err(e) => return Ok(Json(serde_json::json!({"error": e}))),
+        },
+    };
 
     // Remove app directory using shell to handle permission issues from container-created files
     let app_dir = format!("/apps/{}", app_name);
     if std::path::Path::new(&app_dir).exists() {
         let rm_status = Command::new("rm").args(["-rf", &app_dir]).status();
         if !rm_status.map(|s| s.success()).unwrap_or(false) {
-            return Json(serde_json::json!({"error": "Failed to remove app directory"}));
+            return Ok(Json(serde_json::json!({"error": "Failed to remove app directory"})));
         }
     }
 
@@ -809,150 +1219,172 @@ async fn delete_app(
         let _ = save_auth_config(&config);
     }
 
-    Json(serde_json::json!({"status": "success"}))
-}
+    // Remove resource limits
+    {
+        let mut config = state.resources_config.write().unwrap();
+        if config.remove(&app_name).is_some() {
+            let _ = save_resources_config(&config);
+        }
+    }
+
+    // Untag retained image revisions
+    state.revisions.remove_app(state.runtime.as_ref(), &app_name).await;
+
+    // Remove build history and logs
+    state.builds.remove_app(&app_name);
+
+    // Remove stored env vars and secrets
+    state.secrets.remove_app(&app_name);
+
+    // Remove declared extra routes
+    {
+        let mut routes = state.routes_config.write().unwrap();
+        if routes.remove(&app_name).is_some() {
+            let _ = save_routes_config(&routes);
+        }
+    }
 
-async fn get_logs(Path(app_name): Path<String>) -> String {
-    let output = Command::new("docker")
-        .args(["logs", "--tail", "100", &app_name])
-        .output()
-        .expect("Failed to execute docker logs");
+    Ok(Json(serde_json::json!({"status": "success", "purged_volumes": purged})))
+}
 
-    String::from_utf8_lossy(&output.stdout).to_string() + &String::from_utf8_lossy(&output.stderr)
+async fn get_logs(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> String {
+    let names = state.status.replica_names(&app_name);
+    let mut out = String::new();
+    for name in &names {
+        if names.len() > 1 {
+            out.push_str(&format!("── {} ──\n", name));
+        }
+        match state.runtime.container_logs(name, 100).await {
+            Ok(logs) => out.push_str(&logs),
+            Err(e) => out.push_str(&format!("Failed to fetch logs: {}\n", e)),
+        }
+    }
+    out
 }
 
 async fn rebuild_app(
     State(state): State<Arc<AppState>>,
     Path(app_name): Path<String>,
-) -> impl IntoResponse {
+    Query(query): Query<WaitQuery>,
+    Query(options): Query<rollout::DeployOptions>,
+) -> Result<impl IntoResponse, LockConflict> {
+    let guard = if query.wait {
+        None
+    } else {
+        Some(state.locks.try_acquire(&app_name, "rebuild")?)
+    };
+
     let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(256);
-    let manager_ip = state.manager_ip.clone();
 
     tokio::spawn(async move {
-        let app_dir = format!("/apps/{}", app_name);
-
-        // Start docker build with plain progress output for streaming
-        let build_result = TokioCommand::new("docker")
-            .args(["build", "--progress=plain", "-t", &app_name, &app_dir])
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn();
-
-        let mut child = match build_result {
-            Ok(c) => c,
-            Err(e) => {
-                let _ = tx.send(Ok(Event::default().data(format!("Error starting build: {}", e)))).await;
-                let _ = tx.send(Ok(Event::default().event("done").data("failed"))).await;
-                return;
+        // Held until the spawned rebuild finishes
+        let _guard = match guard {
+            Some(g) => g,
+            None => {
+                let _ = tx.send(Ok(Event::default().data(format!(
+                    "Waiting for other operations on {} to finish...", app_name
+                )))).await;
+                state.locks.acquire(&app_name, "rebuild").await
             }
         };
-
-        let stderr = child.stderr.take();
-        let stdout = child.stdout.take();
-
-        // Stream stderr (main docker build output with --progress=plain)
-        let tx_stderr = tx.clone();
-        let stderr_handle = tokio::spawn(async move {
-            if let Some(stderr) = stderr {
-                let mut lines = tokio::io::BufReader::new(stderr).lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    if tx_stderr.send(Ok(Event::default().data(line))).await.is_err() {
-                        break;
-                    }
+        let build = state.builds.start(&app_name, builds::Trigger::Rebuild);
+        let _ = tx.send(Ok(Event::default().event("build").data(build.id()))).await;
+
+        // Build and container messages go out as plain data, readiness progress
+        // as "readiness" events, all ahead of "done"
+        let (log_tx, mut log_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let log = build.tee(log_tx);
+        let progress = build.tee(progress_tx);
+        let tx_forward = tx.clone();
+        let forward_handle = tokio::spawn(async move {
+            loop {
+                let event = tokio::select! {
+                    biased;
+                    Some(line) = log_rx.recv() => Event::default().data(line),
+                    Some(line) = progress_rx.recv() => Event::default().event("readiness").data(line),
+                    else => break,
+                };
+                if tx_forward.send(Ok(event)).await.is_err() {
+                    break;
                 }
             }
         });
 
-        // Stream stdout as well
-        let tx_stdout = tx.clone();
-        let stdout_handle = tokio::spawn(async move {
-            if let Some(stdout) = stdout {
-                let mut lines = tokio::io::BufReader::new(stdout).lines();
-                while let Ok(Some(line)) = lines.next_line().await {
-                    if tx_stdout.send(Ok(Event::default().data(line))).await.is_err() {
-                        break;
-                    }
-                }
-            }
-        });
+        let result = run_rebuild(&state, &app_name, options.strategy, &build, &log, &progress).await;
 
-        // Wait for both stream tasks to finish (pipes close when child exits)
-        let _ = tokio::join!(stderr_handle, stdout_handle);
-
-        let exit_status = child.wait().await;
-        match exit_status {
-            Ok(status) if status.success() => {
-                let _ = tx.send(Ok(Event::default().data(
-                    "✓ Build successful. Starting container...".to_string()
-                ))).await;
-
-                // Stop existing container (ignore errors — may not be running)
-                let _ = TokioCommand::new("docker")
-                    .args(["rm", "-f", &app_name])
-                    .output()
-                    .await;
-
-                // Ensure code-server config and extensions directories exist on host for persistence
-                ensure_code_server_host_dirs(&app_name);
-
-                // Build docker run args (mirrors deploy_app)
-                let mut args: Vec<String> = vec![
-                    "run".into(), "-d".into(),
-                    "--name".into(), app_name.clone(),
-                    "--network".into(), "mcp-net".into(),
-                    "-e".into(), format!("APP_NAME={}", app_name),
-                ];
-                add_app_runtime_mounts(&mut args, &app_name);
-                args.push("--label=traefik.enable=true".into());
-                args.push(format!("--label=traefik.http.routers.{}.rule=PathPrefix(`/{}`)", app_name, app_name));
-                args.push(format!("--label=traefik.http.routers.{}.service={}", app_name, app_name));
-                args.push(format!("--label=traefik.http.middlewares.{}-strip.stripprefix.prefixes=/{}/, /{}", app_name, app_name, app_name));
-                args.push(format!("--label=traefik.http.services.{}.loadbalancer.server.port=80", app_name));
-                args.push(format!("--label=traefik.http.middlewares.{}-auth.forwardauth.address=http://{}:8081/api/verify", app_name, manager_ip));
-                args.push(format!("--label=traefik.http.middlewares.{}-auth.forwardauth.authRequestHeaders=X-API-Key,Authorization", app_name));
-                args.push(format!("--label=traefik.http.middlewares.{}-auth.forwardauth.authResponseHeaders=X-Forwarded-User", app_name));
-                args.push(format!("--label=traefik.http.routers.{}.middlewares={}-auth,{}-strip", app_name, app_name, app_name));
-                args.push(format!("--label=traefik.http.routers.{}-ide.rule=PathPrefix(`/{}-ide`)", app_name, app_name));
-                args.push(format!("--label=traefik.http.routers.{}-ide.service={}-ide", app_name, app_name));
-                args.push(format!("--label=traefik.http.middlewares.{}-ide-strip.stripprefix.prefixes=/{}-ide", app_name, app_name));
-                args.push(format!("--label=traefik.http.routers.{}-ide.middlewares={}-ide-strip", app_name, app_name));
-                args.push(format!("--label=traefik.http.services.{}-ide.loadbalancer.server.port=8000", app_name));
-
-                args.push(app_name.clone());
-
-                let run_result = TokioCommand::new("docker")
-                    .args(&args)
-                    .output()
-                    .await;
-
-                match run_result {
-                    Ok(o) if o.status.success() => {
-                        let _ = tx.send(Ok(Event::default().data("✓ Container started successfully".to_string()))).await;
-                        let _ = tx.send(Ok(Event::default().event("done").data("success"))).await;
-                    }
-                    Ok(o) => {
-                        let err = String::from_utf8_lossy(&o.stderr);
-                        let _ = tx.send(Ok(Event::default().data(format!("✗ Container start failed: {}", err)))).await;
-                        let _ = tx.send(Ok(Event::default().event("done").data("failed"))).await;
-                    }
-                    Err(e) => {
-                        let _ = tx.send(Ok(Event::default().data(format!("✗ Container start error: {}", e)))).await;
-                        let _ = tx.send(Ok(Event::default().event("done").data("failed"))).await;
-                    }
-                }
-            }
-            Ok(_) => {
-                let _ = tx.send(Ok(Event::default().event("done").data("failed"))).await;
-            }
-            Err(e) => {
-                let _ = tx.send(Ok(Event::default().data(format!("✗ Build process error: {}", e)))).await;
-                let _ = tx.send(Ok(Event::default().event("done").data("failed"))).await;
+        // Closing the sinks ends the forwarder once buffered lines are flushed
+        drop(log);
+        drop(progress);
+        let _ = forward_handle.await;
+        let done = if result.is_ok() { "success" } else { "failed" };
+        build.finish(result).await;
+        let _ = tx.send(Ok(Event::default().event("done").data(done))).await;
+    });
+
+    Ok(Sse::new(ReceiverStream::new(rx))
+        .keep_alive(axum::response::sse::KeepAlive::default()))
+}
+
+// Failures are reported on `log`/`progress`; the Err only carries the summary
+// stored with the build record
+async fn run_rebuild(
+    state: &AppState,
+    app_name: &str,
+    strategy: Option<DeployStrategy>,
+    build: &builds::BuildRecorder,
+    log: &LogSink,
+    progress: &LogSink,
+) -> Result<(), String> {
+    let manifest = match manifest::load(app_name) {
+        Ok(m) => m,
+        Err(errors) => {
+            for e in &errors {
+                let _ = log.send(format!("✗ {}", e));
             }
+            return Err(manifest_error_text(&errors));
         }
-    });
+    };
+    let run_spec = app_run_spec(state, app_name, &manifest).inspect_err(|e| {
+        let _ = log.send(format!("✗ {}", e));
+    })?;
+
+    let image_id = match state.runtime.build_image(&app_build_spec(app_name), log).await {
+        Ok(id) => id,
+        // The build output already ends with Docker's error
+        Err(runtime::RuntimeError::Build(e)) => return Err(format!("Docker build failed: {}", e)),
+        Err(e) => {
+            let _ = log.send(format!("✗ Build process error: {}", e));
+            return Err(format!("Build process error: {}", e));
+        }
+    };
+    let _ = log.send("✓ Build successful. Starting container...".to_string());
+    build.set_image(&image_id);
+    revisions::record_build(state, app_name, &image_id, "rebuild", log).await;
 
-    Sse::new(ReceiverStream::new(rx))
-        .keep_alive(axum::response::sse::KeepAlive::default())
+    // Ensure code-server config and extensions directories exist on host for persistence
+    ensure_code_server_host_dirs(app_name);
+
+    apply_default_auth(state, app_name, &manifest);
+
+    let readiness = rollout::roll_out(
+        state.runtime.as_ref(), app_name, &manifest, run_spec,
+        strategy.unwrap_or(manifest.deploy_strategy),
+        state.readiness_timeout, log, progress,
+    ).await.inspect_err(|e| {
+        let _ = log.send(format!("✗ {}", e));
+    })?;
+    if let readiness::Readiness::Crashed { logs, .. } = &readiness {
+        for line in logs {
+            let _ = progress.send(format!("  | {}", line));
+        }
+    }
+    let mark = if readiness.is_ready() { "✓" } else { "✗" };
+    let _ = progress.send(format!("{} {}", mark, readiness.summary()));
+    if readiness.is_ready() { Ok(()) } else { Err(readiness.summary()) }
 }
 
 // ── Password helpers ──
@@ -977,14 +1409,15 @@ fn update_cs_config_password(app_name: &str, password_line: &str) -> bool {
     fs::write(&path, new_config).is_ok()
 }
 
-fn restart_code_server(app_name: &str) {
-    let _ = Command::new("docker")
-        .args(["exec", app_name, "pkill", "-f", "code-server"])
-        .status();
-    let _ = Command::new("docker")
-        .args(["exec", "-d", app_name, "sh", "-c",
-            &code_server_start_command(app_name)])
-        .status();
+// Every replica runs its own code-server over the shared config
+async fn restart_code_server(state: &AppState, app_name: &str) {
+    let kill = ["pkill", "-f", "code-server"].map(String::from);
+    let ide_port = manifest::load(app_name).map(|m| m.ports.ide).unwrap_or(traefik::IDE_PORT);
+    let start = ["sh".to_string(), "-c".to_string(), code_server_start_command(app_name, ide_port)];
+    for name in state.status.replica_names(app_name) {
+        let _ = state.runtime.exec(&name, &kill, false).await;
+        let _ = state.runtime.exec(&name, &start, true).await;
+    }
 }
 
 fn hash_password_argon2(password: &str) -> Result<String, String> {
@@ -1035,91 +1468,31 @@ async fn get_password(Path(app_name): Path<String>) -> Json<serde_json::Value> {
     Json(serde_json::json!({"password": password}))
 }
 
-async fn create_app(Path(app_name): Path<String>) -> Json<serde_json::Value> {
+async fn create_app(Path(app_name): Path<String>, Query(query): Query<CreateQuery>) -> Json<serde_json::Value> {
     let app_dir = format!("/apps/{}", app_name);
 
     if std::path::Path::new(&app_dir).exists() {
         return Json(serde_json::json!({"error": "App already exists"}));
     }
-
-    if let Err(e) = fs::create_dir_all(&app_dir) {
-        return Json(serde_json::json!({"error": format!("Failed to create directory: {}", e)}));
+    if !valid_app_name(&app_name) {
+        return Json(serde_json::json!({"error": format!("Invalid app name '{}'", app_name)}));
     }
 
-        let dockerfile = r#"FROM python:3.11-slim
-
-ENV DEBIAN_FRONTEND=noninteractive
-ENV CODE_SERVER_EXTENSIONS_DIR=/root/.local/share/code-server/extensions
-ENV OFFLINE_VSIX_DIR=/opt/offline-vsix
-
-RUN apt-get update && apt-get install -y curl && rm -rf /var/lib/apt/lists/*
-RUN curl -fsSL https://code-server.dev/install.sh | sh
-
-RUN cat <<'EOF' > /usr/local/bin/start-app.sh
-#!/bin/sh
-set -eu
-
-mkdir -p /root/.config/code-server "${CODE_SERVER_EXTENSIONS_DIR}"
-
-if [ -d "${OFFLINE_VSIX_DIR}" ]; then
-    find "${OFFLINE_VSIX_DIR}" -maxdepth 1 -type f \( -name '*.vsix' -o -name '*.VSIX' \) | sort | while read -r vsix; do
-        [ -n "${vsix}" ] || continue
-        echo "Installing offline VSIX: $(basename "${vsix}")"
-        code-server --install-extension "${vsix}" --extensions-dir "${CODE_SERVER_EXTENSIONS_DIR}" --force
-    done
-fi
-
-code-server --auth password --bind-addr 0.0.0.0:8000 --abs-proxy-base-path "/${APP_NAME}-ide" --extensions-dir "${CODE_SERVER_EXTENSIONS_DIR}" /app &
-exec uvicorn app:app --host 0.0.0.0 --port 80 --reload
-EOF
-RUN chmod +x /usr/local/bin/start-app.sh
-
-WORKDIR /app
-COPY requirements.txt .
-RUN pip install --no-cache-dir -r requirements.txt
-COPY . .
-
-ENV APP_NAME=app
-
-CMD ["/usr/local/bin/start-app.sh"]
-"#;
-
-    let requirements = "fastapi\nuvicorn\npython-multipart\n";
-
-    let app_py = r#"from fastapi import FastAPI, File, UploadFile
-import shutil
-import os
-
-app = FastAPI()
-UPLOAD_DIR = "uploads"
-os.makedirs(UPLOAD_DIR, exist_ok=True)
-
-@app.get("/")
-def read_root():
-    return {"message": "Hello from the new MCP App!"}
-
-@app.post("/upload")
-async def upload_file(file: UploadFile = File(...)):
-    file_path = os.path.join(UPLOAD_DIR, file.filename)
-    with open(file_path, "wb") as buffer:
-        shutil.copyfileobj(file.file, buffer)
-    return {"status": "success", "filename": file.filename}
-"#;
-
-    let files = [
-        ("Dockerfile", dockerfile),
-        ("requirements.txt", requirements),
-        ("app.py", app_py),
-    ];
-
-    for (name, content) in &files {
-        let path = format!("{}/{}", app_dir, name);
-        if let Err(e) = fs::write(&path, content) {
-            return Json(serde_json::json!({"error": format!("Failed to write {}: {}", name, e)}));
+    if let Some(url) = &query.git_url {
+        if query.template.is_some() {
+            return Json(serde_json::json!({"error": "git_url and template can't be combined"}));
         }
+        return match git::clone_into(git::APPS_ROOT, &app_name, url, query.branch.as_deref()).await {
+            Ok(info) => Json(serde_json::json!({"status": "success", "git": info})),
+            Err(e) => Json(serde_json::json!({"error": e})),
+        };
     }
 
-    Json(serde_json::json!({"status": "success"}))
+    let template = query.template.as_deref().unwrap_or(templates::DEFAULT_TEMPLATE);
+    match templates::scaffold(&app_name, template, &query.variables) {
+        Ok(()) => Json(serde_json::json!({"status": "success", "template": template})),
+        Err(e) => Json(serde_json::json!({"error": e})),
+    }
 }
 
 async fn app_dashboard(
@@ -1319,6 +1692,16 @@ function startRebuild() {{
     terminal.scrollTop = terminal.scrollHeight;
     lineCount.textContent = lineNum + " lines";
   }};
+  es.addEventListener("readiness", function(e) {{
+    lineNum++;
+    const line = document.createElement("div");
+    line.className = "line";
+    line.style.color = "#93c5fd";
+    line.textContent = e.data;
+    terminal.appendChild(line);
+    terminal.scrollTop = terminal.scrollHeight;
+    lineCount.textContent = lineNum + " lines";
+  }});
   es.addEventListener("done", function(e) {{
     const success = e.data === "success";
     status.className = success ? "status status-success" : "status status-failed";
@@ -1392,34 +1775,41 @@ async fn verify_app_password(
     }
 }
 
-async fn reset_password(Path(app_name): Path<String>) -> Json<serde_json::Value> {
+async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Query(query): Query<WaitQuery>,
+) -> Result<Json<serde_json::Value>, LockConflict> {
+    let _guard = state.locks.lock(&app_name, "password reset", query.wait).await?;
     let new_password = format!("{:016x}", rand::random::<u64>());
 
     // Write as plaintext so admin can view it later
     let password_line = format!("password: {}", new_password);
     if update_cs_config_password(&app_name, &password_line) {
-        restart_code_server(&app_name);
-        Json(serde_json::json!({"password": new_password}))
+        restart_code_server(&state, &app_name).await;
+        Ok(Json(serde_json::json!({"password": new_password})))
     } else {
-        Json(serde_json::json!({"error": "Failed to reset password"}))
+        Ok(Json(serde_json::json!({"error": "Failed to reset password"})))
     }
 }
 
 async fn set_password(
+    State(state): State<Arc<AppState>>,
     Path(app_name): Path<String>,
+    Query(query): Query<WaitQuery>,
     Json(body): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
+) -> Result<Json<serde_json::Value>, LockConflict> {
     let current_pw = body.get("current_password").and_then(|v| v.as_str()).unwrap_or("");
     let new_pw = body.get("new_password").and_then(|v| v.as_str()).unwrap_or("");
 
     if current_pw.is_empty() || new_pw.is_empty() {
-        return Json(serde_json::json!({"error": "Current and new passwords are required"}));
+        return Ok(Json(serde_json::json!({"error": "Current and new passwords are required"})));
     }
 
     // Verify current password
     let config = match read_cs_config(&app_name) {
         Some(c) => c,
-        None => return Json(serde_json::json!({"error": "Config not found"})),
+        None => return Ok(Json(serde_json::json!({"error": "Config not found"}))),
     };
 
     let verified = if let Some(hash_line) = config.lines().find(|l| l.starts_with("hashed-password:")) {
@@ -1434,21 +1824,23 @@ async fn set_password(
     };
 
     if !verified {
-        return Json(serde_json::json!({"error": "Current password is incorrect"}));
+        return Ok(Json(serde_json::json!({"error": "Current password is incorrect"})));
     }
 
+    let _guard = state.locks.lock(&app_name, "password change", query.wait).await?;
+
     // Hash and set new password
     let hashed = match hash_password_argon2(new_pw) {
         Ok(h) => h,
-        Err(e) => return Json(serde_json::json!({"error": format!("Hash failed: {}", e)})),
+        Err(e) => return Ok(Json(serde_json::json!({"error": format!("Hash failed: {}", e)}))),
     };
 
     let password_line = format!("hashed-password: \"{}\"", hashed);
     if update_cs_config_password(&app_name, &password_line) {
-        restart_code_server(&app_name);
-        Json(serde_json::json!({"status": "ok"}))
+        restart_code_server(&state, &app_name).await;
+        Ok(Json(serde_json::json!({"status": "ok"})))
     } else {
-        Json(serde_json::json!({"error": "Failed to set password"}))
+        Ok(Json(serde_json::json!({"error": "Failed to set password"})))
     }
 }
 
@@ -1456,26 +1848,7 @@ async fn set_password(
 
 async fn list_groups(State(state): State<Arc<AppState>>) -> Json<Vec<GroupResponse>> {
     let groups = state.groups.read().unwrap().clone();
-    let running = get_running_containers();
-
-    // Collect all app directories
-    let paths = match fs::read_dir("/apps") {
-        Ok(p) => p,
-        Err(_) => return Json(vec![]),
-    };
-    let mut all_containers: Vec<String> = vec![];
-    for path in paths {
-        if let Ok(entry) = path {
-            if let Ok(ft) = entry.file_type() {
-                if ft.is_dir() {
-                    let name = entry.file_name().to_string_lossy().to_string();
-                    if !name.starts_with('.') && name != "auth_config.json" && name != "groups_config.json" {
-                        all_containers.push(name);
-                    }
-                }
-            }
-        }
-    }
+    let all_containers = app_dir_names();
 
     // Containers assigned to any real group
     let assigned: std::collections::HashSet<&str> = groups.iter()
@@ -1492,7 +1865,7 @@ async fn list_groups(State(state): State<Arc<AppState>>) -> Json<Vec<GroupRespon
 
     // Virtual "Default" group for ungrouped containers (always shown if non-empty)
     if !ungrouped.is_empty() {
-        let summary = compute_summary(&ungrouped, &running);
+        let summary = compute_summary(&state, &ungrouped);
         let now = now_iso8601();
         responses.push(GroupResponse {
             id: "default".to_string(),
@@ -1500,6 +1873,7 @@ async fn list_groups(State(state): State<Arc<AppState>>) -> Json<Vec<GroupRespon
             description: "未所属のコンテナ".to_string(),
             containers: ungrouped,
             container_summary: summary,
+            resources: Resources::default(),
             created_at: now.clone(),
             updated_at: now,
         });
@@ -1507,13 +1881,14 @@ async fn list_groups(State(state): State<Arc<AppState>>) -> Json<Vec<GroupRespon
 
     // Real groups
     for g in &groups {
-        let summary = compute_summary(&g.containers, &running);
+        let summary = compute_summary(&state, &g.containers);
         responses.push(GroupResponse {
             id: g.id.clone(),
             name: g.name.clone(),
             description: g.description.clone(),
             containers: g.containers.clone(),
             container_summary: summary,
+            resources: g.resources.clone(),
             created_at: g.created_at.clone(),
             updated_at: g.updated_at.clone(),
         });
@@ -1526,11 +1901,15 @@ async fn create_group(
     State(state): State<Arc<AppState>>,
     Json(body): Json<CreateGroupRequest>,
 ) -> Json<serde_json::Value> {
+    if let Err(e) = state.ceiling.check(&body.resources) {
+        return Json(serde_json::json!({"error": e}));
+    }
     let new_group = Group {
         id: format!("{:016x}", rand::random::<u64>()),
         name: body.name,
         description: body.description,
         containers: vec![],
+        resources: body.resources,
         created_at: now_iso8601(),
         updated_at: now_iso8601(),
     };
@@ -1547,11 +1926,19 @@ async fn update_group(
     Path(id): Path<String>,
     Json(body): Json<UpdateGroupRequest>,
 ) -> Json<serde_json::Value> {
+    if let Some(resources) = &body.resources
+        && let Err(e) = state.ceiling.check(resources)
+    {
+        return Json(serde_json::json!({"error": e}));
+    }
     let mut groups = state.groups.write().unwrap();
     match groups.iter_mut().find(|g| g.id == id) {
         Some(group) => {
             group.name = body.name;
             group.description = body.description;
+            if let Some(resources) = body.resources {
+                group.resources = resources;
+            }
             group.updated_at = now_iso8601();
             match save_groups(&groups) {
                 Ok(_) => Json(serde_json::json!({"status": "ok"})),
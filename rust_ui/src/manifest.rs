@@ -0,0 +1,625 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use toml::Spanned;
+
+use crate::traefik::{self, ExtraRoute};
+
+// ── Per-app manifest (/apps/{app}/mcphub.toml) ──
+//
+// Optional file next to the Dockerfile. Everything in it has a default, so an
+// app without a manifest deploys exactly as before:
+//
+//   network = "mcp-net"
+//
+//   [ports]
+//   api = 80
+//   ide = 8000
+//
+//   [env]
+//   LOG_LEVEL = "debug"
+//
+//   [healthcheck]
+//   path = "/openapi.json"
+//   interval = 10
+//   timeout = 5
+//   retries = 3
+//
+//   [resources]
+//   memory = "512m"
+//   cpus = 1.5
+//   pids = 256
+//
+//   [auth]
+//   type = "api_key"
+//
+//   [restart]
+//   policy = "on-failure"   # never | on-failure | always
+//   max_retries = 5
+//
+//   [deploy]
+//   strategy = "blue-green"  # recreate | blue-green
+//
+//   [scale]
+//   replicas = 3             # {app}-1..3 behind one Traefik service
+//   idle_timeout = 1800      # stop after this many seconds without API requests
+//
+//   [volumes]                # persistent named volumes: name = mount path
+//   data = "/data"
+//
+//   [[routes]]
+//   name = "ws"
+//   path = "/ws"
+//   port = 8765
+
+pub const MANIFEST_FILE: &str = "mcphub.toml";
+pub const DEFAULT_NETWORK: &str = "mcp-net";
+pub const MAX_REPLICAS: u32 = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} line {}: {}", MANIFEST_FILE, line, self.message),
+            None => write!(f, "{}: {}", MANIFEST_FILE, self.message),
+        }
+    }
+}
+
+// ── File format ──
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ManifestFile {
+    network: Option<Spanned<String>>,
+    ports: Option<PortsSection>,
+    #[serde(default)]
+    env: BTreeMap<String, Spanned<String>>,
+    healthcheck: Option<HealthcheckSection>,
+    resources: Option<ResourcesSection>,
+    auth: Option<AuthSection>,
+    restart: Option<RestartSection>,
+    deploy: Option<DeploySection>,
+    scale: Option<ScaleSection>,
+    #[serde(default)]
+    volumes: BTreeMap<String, Spanned<String>>,
+    #[serde(default)]
+    routes: Vec<Spanned<ExtraRoute>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PortsSection {
+    api: Option<Spanned<u16>>,
+    ide: Option<Spanned<u16>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HealthcheckSection {
+    path: Spanned<String>,
+    interval: Option<Spanned<u64>>,
+    timeout: Option<Spanned<u64>>,
+    retries: Option<Spanned<u32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResourcesSection {
+    memory: Option<Spanned<String>>,
+    cpus: Option<Spanned<f64>>,
+    pids: Option<Spanned<i64>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AuthSection {
+    #[serde(rename = "type")]
+    auth_type: Spanned<String>,
+    api_key: Option<String>,
+    tenant_id: Option<String>,
+    client_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RestartSection {
+    policy: Spanned<String>,
+    max_retries: Option<Spanned<u32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeploySection {
+    strategy: Spanned<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScaleSection {
+    replicas: Option<Spanned<u32>>,
+    idle_timeout: Option<Spanned<u64>>,
+}
+
+// ── Effective config ──
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Ports {
+    pub api: u16,
+    pub ide: u16,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Healthcheck {
+    pub path: String,
+    pub interval_secs: u64,
+    pub timeout_secs: u64,
+    pub retries: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Resources {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DefaultAuth {
+    None,
+    ApiKey { api_key: Option<String> },
+    EntraId { tenant_id: String, client_id: String },
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+#[serde(tag = "policy", rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure { max_retries: Option<u32> },
+    Always,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeployStrategy {
+    // Remove the old container, then start the new one
+    #[default]
+    Recreate,
+    // Start {app}_green next to the old container and swap once it is ready
+    BlueGreen,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppManifest {
+    // Whether /apps/{app}/mcphub.toml exists (otherwise all defaults)
+    pub present: bool,
+    pub network: String,
+    pub ports: Ports,
+    pub env: BTreeMap<String, String>,
+    pub healthcheck: Option<Healthcheck>,
+    pub resources: Resources,
+    pub auth: Option<DefaultAuth>,
+    pub restart: RestartPolicy,
+    pub deploy_strategy: DeployStrategy,
+    pub replicas: u32,
+    // Scale to zero after this long without API requests
+    pub idle_timeout_secs: Option<u64>,
+    // Volume name -> mount path in the container
+    pub volumes: BTreeMap<String, String>,
+    pub routes: Vec<ExtraRoute>,
+}
+
+impl Default for AppManifest {
+    fn default() -> Self {
+        AppManifest {
+            present: false,
+            network: DEFAULT_NETWORK.to_string(),
+            ports: Ports { api: traefik::API_PORT, ide: traefik::IDE_PORT },
+            env: BTreeMap::new(),
+            healthcheck: None,
+            resources: Resources::default(),
+            auth: None,
+            restart: RestartPolicy::Never,
+            deploy_strategy: DeployStrategy::Recreate,
+            replicas: 1,
+            idle_timeout_secs: None,
+            volumes: BTreeMap::new(),
+            routes: Vec::new(),
+        }
+    }
+}
+
+pub fn manifest_path(app_name: &str) -> String {
+    format!("/apps/{}/{}", app_name, MANIFEST_FILE)
+}
+
+// Reads and validates the app's manifest; a missing file yields the defaults
+pub fn load(app_name: &str) -> Result<AppManifest, Vec<ManifestError>> {
+    match std::fs::read_to_string(manifest_path(app_name)) {
+        Ok(src) => parse(&src),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(AppManifest::default()),
+        Err(e) => Err(vec![ManifestError { line: None, message: format!("failed to read: {}", e) }]),
+    }
+}
+
+fn line_of(src: &str, offset: usize) -> usize {
+    src[..offset.min(src.len())].matches('\n').count() + 1
+}
+
+// Parses memory sizes like "512m", "1.5g", "2048k" or plain bytes
+pub fn parse_memory(value: &str) -> Option<i64> {
+    let v = value.trim().to_ascii_lowercase();
+    let v = v.strip_suffix('b').unwrap_or(&v);
+    let (number, multiplier) = match v.chars().last()? {
+        'k' => (&v[..v.len() - 1], 1024f64),
+        'm' => (&v[..v.len() - 1], 1024f64 * 1024.0),
+        'g' => (&v[..v.len() - 1], 1024f64 * 1024.0 * 1024.0),
+        _ => (v, 1f64),
+    };
+    let n: f64 = number.trim().parse().ok()?;
+    if !n.is_finite() || n <= 0.0 {
+        return None;
+    }
+    Some((n * multiplier) as i64)
+}
+
+// Lowercase letters, digits, '-' and '_', starting with a letter or digit
+pub fn valid_volume_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_lowercase() || c.is_ascii_digit())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        && name.len() <= 32
+}
+
+// Mount points the manager already uses for source and IDE state
+const RESERVED_MOUNTS: &[&str] = &["/app", "/root/.local/share/code-server/extensions", "/root/.config/code-server", "/opt/offline-vsix"];
+
+pub fn valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn parse(src: &str) -> Result<AppManifest, Vec<ManifestError>> {
+    let file: ManifestFile = toml::from_str(src).map_err(|e| {
+        vec![ManifestError {
+            line: e.span().map(|s| line_of(src, s.start)),
+            message: e.message().trim().to_string(),
+        }]
+    })?;
+
+    let mut errors = Vec::new();
+    let mut err = |span: std::ops::Range<usize>, message: String| {
+        errors.push(ManifestError { line: Some(line_of(src, span.start)), message });
+    };
+    let mut manifest = AppManifest { present: true, ..Default::default() };
+
+    if let Some(network) = file.network {
+        if network.get_ref().trim().is_empty() {
+            err(network.span(), "network must not be empty".into());
+        }
+        manifest.network = network.into_inner();
+    }
+
+    if let Some(ports) = file.ports {
+        let mut last_span = None;
+        for (field, port, target) in [
+            ("api", ports.api, &mut manifest.ports.api),
+            ("ide", ports.ide, &mut manifest.ports.ide),
+        ] {
+            if let Some(port) = port {
+                if *port.get_ref() == 0 {
+                    err(port.span(), format!("ports.{} must be between 1 and 65535", field));
+                }
+                last_span = Some(port.span());
+                *target = port.into_inner();
+            }
+        }
+        if let Some(span) = last_span
+            && manifest.ports.api == manifest.ports.ide
+        {
+            err(span, "ports.api and ports.ide must differ".into());
+        }
+    }
+
+    for (key, value) in file.env {
+        if !valid_env_name(&key) {
+            err(value.span(), format!("env key '{}' is not a valid variable name", key));
+        } else if key == "APP_NAME" {
+            err(value.span(), "env.APP_NAME is set by the manager and cannot be overridden".into());
+        }
+        manifest.env.insert(key, value.into_inner());
+    }
+
+    if let Some(hc) = file.healthcheck {
+        if !hc.path.get_ref().starts_with('/') {
+            err(hc.path.span(), "healthcheck.path must start with '/'".into());
+        }
+        let mut check = Healthcheck {
+            path: hc.path.into_inner(),
+            interval_secs: 10,
+            timeout_secs: 5,
+            retries: 3,
+        };
+        if let Some(interval) = hc.interval {
+            if *interval.get_ref() == 0 {
+                err(interval.span(), "healthcheck.interval must be at least 1 second".into());
+            }
+            check.interval_secs = interval.into_inner();
+        }
+        if let Some(timeout) = hc.timeout {
+            if *timeout.get_ref() == 0 {
+                err(timeout.span(), "healthcheck.timeout must be at least 1 second".into());
+            }
+            check.timeout_secs = timeout.into_inner();
+        }
+        if let Some(retries) = hc.retries {
+            check.retries = retries.into_inner();
+        }
+        manifest.healthcheck = Some(check);
+    }
+
+    if let Some(res) = file.resources {
+        if let Some(memory) = res.memory {
+            if parse_memory(memory.get_ref()).is_none() {
+                err(memory.span(), format!("resources.memory '{}' is not a size like \"512m\" or \"2g\"", memory.get_ref()));
+            }
+            manifest.resources.memory = Some(memory.into_inner());
+        }
+        if let Some(cpus) = res.cpus {
+            if !cpus.get_ref().is_finite() || *cpus.get_ref() <= 0.0 {
+                err(cpus.span(), "resources.cpus must be a number greater than 0".into());
+            }
+            manifest.resources.cpus = Some(cpus.into_inner());
+        }
+        if let Some(pids) = res.pids {
+            if *pids.get_ref() < 1 {
+                err(pids.span(), "resources.pids must be at least 1".into());
+            }
+            manifest.resources.pids = Some(pids.into_inner());
+        }
+    }
+
+    if let Some(auth) = file.auth {
+        let span = auth.auth_type.span();
+        manifest.auth = match auth.auth_type.get_ref().as_str() {
+            "none" => Some(DefaultAuth::None),
+            "api_key" => Some(DefaultAuth::ApiKey { api_key: auth.api_key }),
+            "entra_id" => match (auth.tenant_id, auth.client_id) {
+                (Some(tenant_id), Some(client_id)) => Some(DefaultAuth::EntraId { tenant_id, client_id }),
+                _ => {
+                    err(span, "auth type \"entra_id\" requires tenant_id and client_id".into());
+                    None
+                }
+            },
+            other => {
+                err(span, format!("unknown auth type '{}' (expected none, api_key or entra_id)", other));
+                None
+            }
+        };
+    }
+
+    if let Some(restart) = file.restart {
+        let max_retries = restart.max_retries.map(|m| (m.span(), m.into_inner()));
+        let policy = match restart.policy.get_ref().as_str() {
+            "never" => Some(RestartPolicy::Never),
+            "always" => Some(RestartPolicy::Always),
+            "on-failure" => Some(RestartPolicy::OnFailure { max_retries: max_retries.as_ref().map(|(_, m)| *m) }),
+            other => {
+                err(restart.policy.span(), format!("unknown restart policy '{}' (expected never, on-failure or always)", other));
+                None
+            }
+        };
+        if let Some((span, _)) = max_retries
+            && matches!(policy, Some(RestartPolicy::Never | RestartPolicy::Always))
+        {
+            err(span, "restart.max_retries only applies to policy \"on-failure\"".into());
+        }
+        manifest.restart = policy.unwrap_or_default();
+    }
+
+    if let Some(deploy) = file.deploy {
+        manifest.deploy_strategy = match deploy.strategy.get_ref().as_str() {
+            "recreate" => DeployStrategy::Recreate,
+            "blue-green" => DeployStrategy::BlueGreen,
+            other => {
+                err(deploy.strategy.span(), format!("unknown deploy strategy '{}' (expected recreate or blue-green)", other));
+                DeployStrategy::Recreate
+            }
+        };
+    }
+
+    if let Some(scale) = file.scale {
+        if let Some(replicas) = scale.replicas {
+            if !(1..=MAX_REPLICAS).contains(replicas.get_ref()) {
+                err(replicas.span(), format!("scale.replicas must be between 1 and {}", MAX_REPLICAS));
+            }
+            manifest.replicas = replicas.into_inner();
+        }
+        if let Some(idle_timeout) = scale.idle_timeout {
+            if *idle_timeout.get_ref() == 0 {
+                err(idle_timeout.span(), "scale.idle_timeout must be at least 1 second".into());
+            }
+            manifest.idle_timeout_secs = Some(idle_timeout.into_inner());
+        }
+    }
+
+    for (name, path) in file.volumes {
+        let mount = path.get_ref().trim_end_matches('/');
+        if !valid_volume_name(&name) {
+            err(path.span(), format!("volume name '{}' must be lowercase letters, digits, '-' or '_' (at most 32)", name));
+        } else if !path.get_ref().starts_with('/') || mount.is_empty() || mount.split('/').any(|p| p == "..") {
+            err(path.span(), format!("volumes.{} must be an absolute path other than '/'", name));
+        } else if RESERVED_MOUNTS.iter().any(|r| mount == *r || mount.starts_with(&format!("{}/", r)) || r.starts_with(&format!("{}/", mount))) {
+            err(path.span(), format!("volumes.{} cannot be mounted at {}, which the manager uses", name, mount));
+        } else if manifest.volumes.values().any(|m| m == mount) {
+            err(path.span(), format!("volumes.{} mounts the same path as another volume", name));
+        }
+        manifest.volumes.insert(name, mount.to_string());
+    }
+
+    for (i, route) in file.routes.iter().enumerate() {
+        if let Err(e) = route.get_ref().validate() {
+            err(route.span(), e);
+        } else if file.routes[..i].iter().any(|r| r.get_ref().name == route.get_ref().name) {
+            err(route.span(), format!("duplicate route name '{}'", route.get_ref().name));
+        }
+    }
+    manifest.routes = file.routes.into_iter().map(|r| r.into_inner()).collect();
+
+    if errors.is_empty() {
+        Ok(manifest)
+    } else {
+        errors.sort_by_key(|e| e.line);
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(src: &str) -> Vec<(Option<usize>, String)> {
+        match parse(src) {
+            Ok(_) => panic!("expected errors for:\n{}", src),
+            Err(errors) => errors.into_iter().map(|e| (e.line, e.message)).collect(),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_manifests() {
+        // (manifest, line of the first error, part of its message)
+        let cases: &[(&str, usize, &str)] = &[
+            // Unknown keys, at the top level and in sections
+            ("nework = \"x\"\n", 1, "unknown field `nework`"),
+            ("network = \"x\"\n\n[ports]\napi = 80\nweb = 81\n", 5, "unknown field `web`"),
+            ("[deploy]\nstrategy = \"recreate\"\nreplicas = 2\n", 3, "unknown field `replicas`"),
+            // Ports
+            ("[ports]\napi = 0\n", 2, "ports.api must be between 1 and 65535"),
+            ("[ports]\nide = 70000\n", 2, "invalid value"),
+            ("[ports]\napi = \"80\"\n", 2, "invalid type"),
+            ("[ports]\napi = 8000\nide = 8000\n", 3, "ports.api and ports.ide must differ"),
+            // Memory
+            ("[resources]\nmemory = \"lots\"\n", 2, "resources.memory 'lots' is not a size"),
+            ("[resources]\nmemory = \"-1g\"\n", 2, "is not a size"),
+            ("[resources]\ncpus = \"two\"\n", 2, "invalid type"),
+            ("[resources]\ncpus = 0.0\n", 2, "resources.cpus must be a number greater than 0"),
+            ("[resources]\ncpus = nan\n", 2, "resources.cpus must be a number greater than 0"),
+            ("[resources]\ncpus = inf\n", 2, "resources.cpus must be a number greater than 0"),
+            ("[resources]\ncpus = -inf\n", 2, "resources.cpus must be a number greater than 0"),
+            // Env names
+            ("[env]\n1ABC = \"x\"\n", 2, "env key '1ABC' is not a valid variable name"),
+            ("[env]\n\"MY-VAR\" = \"x\"\n", 2, "env key 'MY-VAR' is not a valid variable name"),
+            ("[env]\nAPP_NAME = \"x\"\n", 2, "env.APP_NAME is set by the manager"),
+            // Everything else
+            ("[healthcheck]\npath = \"health\"\n", 2, "healthcheck.path must start with '/'"),
+            ("[auth]\ntype = \"basic\"\n", 2, "unknown auth type 'basic'"),
+            ("[restart]\npolicy = \"always\"\nmax_retries = 3\n", 3, "only applies to policy \"on-failure\""),
+            ("[deploy]\nstrategy = \"canary\"\n", 2, "unknown deploy strategy 'canary'"),
+            ("[scale]\nreplicas = 21\n", 2, "scale.replicas must be between 1 and 20"),
+            ("[volumes]\ndata = \"/app/data\"\n", 2, "which the manager uses"),
+            ("[volumes]\nData = \"/data\"\n", 2, "volume name 'Data'"),
+        ];
+        for (src, line, message) in cases {
+            let errors = errors(src);
+            assert_eq!(errors[0].0, Some(*line), "line for {:?}: {:?}", src, errors);
+            assert!(errors[0].1.contains(message), "message for {:?}: {:?}", src, errors);
+        }
+    }
+
+    #[test]
+    fn reports_every_error_in_line_order() {
+        let src = "[scale]\nreplicas = 0\n\n[env]\nbad-name = \"x\"\n\n[ports]\napi = 0\n";
+        let lines: Vec<Option<usize>> = errors(src).into_iter().map(|(line, _)| line).collect();
+        assert_eq!(lines, vec![Some(2), Some(5), Some(8)]);
+    }
+
+    #[test]
+    fn empty_manifest_is_all_defaults() {
+        let manifest = parse("").unwrap();
+        assert!(manifest.present);
+        assert_eq!(manifest.network, DEFAULT_NETWORK);
+        assert_eq!((manifest.ports.api, manifest.ports.ide), (traefik::API_PORT, traefik::IDE_PORT));
+        assert_eq!(manifest.replicas, 1);
+        assert_eq!(manifest.restart, RestartPolicy::Never);
+        assert_eq!(manifest.deploy_strategy, DeployStrategy::Recreate);
+        assert!(manifest.healthcheck.is_none() && manifest.auth.is_none() && manifest.volumes.is_empty());
+    }
+
+    #[test]
+    fn merges_settings_over_defaults() {
+        let src = r#"
+network = "custom-net"
+
+[ports]
+api = 8080
+
+[env]
+LOG_LEVEL = "debug"
+
+[healthcheck]
+path = "/health"
+retries = 5
+
+[resources]
+memory = "512m"
+cpus = 1.5
+
+[restart]
+policy = "on-failure"
+max_retries = 4
+
+[deploy]
+strategy = "blue-green"
+
+[scale]
+replicas = 3
+idle_timeout = 600
+
+[volumes]
+data = "/data/"
+"#;
+        let manifest = parse(src).unwrap();
+        assert_eq!(manifest.network, "custom-net");
+        // Only the port that was set changes
+        assert_eq!((manifest.ports.api, manifest.ports.ide), (8080, traefik::IDE_PORT));
+        assert_eq!(manifest.env.get("LOG_LEVEL").map(String::as_str), Some("debug"));
+        let hc = manifest.healthcheck.unwrap();
+        assert_eq!((hc.path.as_str(), hc.interval_secs, hc.timeout_secs, hc.retries), ("/health", 10, 5, 5));
+        assert_eq!(manifest.resources, Resources { memory: Some("512m".to_string()), cpus: Some(1.5), pids: None });
+        assert_eq!(manifest.restart, RestartPolicy::OnFailure { max_retries: Some(4) });
+        assert_eq!(manifest.deploy_strategy, DeployStrategy::BlueGreen);
+        assert_eq!(manifest.replicas, 3);
+        assert_eq!(manifest.idle_timeout_secs, Some(600));
+        // Trailing slashes are dropped from mount paths
+        assert_eq!(manifest.volumes.get("data").map(String::as_str), Some("/data"));
+    }
+
+    #[test]
+    fn memory_sizes() {
+        let cases = [
+            ("512m", Some(512 * 1024 * 1024)),
+            ("1.5g", Some(1536 * 1024 * 1024)),
+            ("2048K", Some(2048 * 1024)),
+            ("64mb", Some(64 * 1024 * 1024)),
+            ("1000", Some(1000)),
+            ("0", None),
+            ("", None),
+            ("m", None),
+            ("ten", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(parse_memory(value), expected, "{:?}", value);
+        }
+    }
+}
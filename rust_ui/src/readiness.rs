@@ -0,0 +1,191 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use crate::manifest::AppManifest;
+use crate::runtime::{ContainerDetails, ContainerRuntime, LogSink, RuntimeError};
+
+// ── Readiness gating ──
+//
+// After a container starts, poll its API and IDE ports until both answer,
+// the container exits or restarts, or the timeout passes. The API probe uses the
+// manifest's healthcheck path (must return 2xx) or /openapi.json (any
+// non-5xx response counts, so apps without OpenAPI still become ready).
+
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const PROGRESS_EVERY: Duration = Duration::from_secs(10);
+const CRASH_LOG_LINES: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Readiness {
+    Ready { elapsed_secs: u64 },
+    Crashed { exit_code: Option<i64>, logs: Vec<String> },
+    Timeout { timeout_secs: u64, waiting_for: Vec<String> },
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        matches!(self, Readiness::Ready { .. })
+    }
+
+    pub fn summary(&self) -> String {
+        match self {
+            Readiness::Ready { elapsed_secs } => format!("App is ready ({}s)", elapsed_secs),
+            Readiness::Crashed { exit_code: Some(code), .. } => format!("App crashed during startup (exit code {})", code),
+            Readiness::Crashed { exit_code: None, .. } => "App container disappeared during startup".to_string(),
+            Readiness::Timeout { timeout_secs, waiting_for } => format!(
+                "App did not become ready within {}s (waiting for {})",
+                timeout_secs,
+                waiting_for.join(", ")
+            ),
+        }
+    }
+}
+
+struct Probe {
+    name: &'static str,
+    port: u16,
+    path: String,
+    // Require a 2xx instead of any non-5xx response
+    strict: bool,
+    ready: bool,
+}
+
+pub fn timeout_from_env() -> Duration {
+    let secs = std::env::var("READINESS_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+fn probes_for(manifest: &AppManifest) -> Vec<Probe> {
+    let (path, strict) = match &manifest.healthcheck {
+        Some(hc) => (hc.path.clone(), true),
+        None => ("/openapi.json".to_string(), false),
+    };
+    vec![
+        Probe { name: "api", port: manifest.ports.api, path, strict, ready: false },
+        Probe { name: "ide", port: manifest.ports.ide, path: "/".to_string(), strict: false, ready: false },
+    ]
+}
+
+// Exit code of a container that went down since the wait began: stopped,
+// restarting, or restarted already (its restart policy brought it back up)
+fn crash_exit_code(details: &ContainerDetails, restarts_before: i64) -> Option<i64> {
+    let crashed = !details.running || details.state == "restarting" || details.restart_count > restarts_before;
+    crashed.then_some(details.exit_code)
+}
+
+async fn crashed(runtime: &dyn ContainerRuntime, container: &str, exit_code: Option<i64>) -> Readiness {
+    let logs = runtime
+        .container_logs(container, CRASH_LOG_LINES)
+        .await
+        .map(|l| l.lines().map(|s| s.to_string()).collect())
+        .unwrap_or_default();
+    Readiness::Crashed { exit_code, logs }
+}
+
+// Progress lines go to `progress`; the final state is returned
+pub async fn wait_ready(
+    runtime: &dyn ContainerRuntime,
+    container: &str,
+    manifest: &AppManifest,
+    timeout: Duration,
+    progress: &LogSink,
+) -> Readiness {
+    let started = Instant::now();
+    let mut last_progress = Instant::now();
+    let mut probes = probes_for(manifest);
+    let network = Some(manifest.network.as_str());
+    // Restart count when the wait began; None until the first inspect
+    let mut restarts_before = None;
+
+    let _ = progress.send(format!(
+        "Waiting for {} to become ready (api :{}{}, ide :{})...",
+        container, probes[0].port, probes[0].path, probes[1].port
+    ));
+
+    loop {
+        match runtime.inspect_container(container).await {
+            Ok(details) => {
+                let before = *restarts_before.get_or_insert(details.restart_count);
+                if let Some(code) = crash_exit_code(&details, before) {
+                    return crashed(runtime, container, Some(code)).await;
+                }
+            }
+            Err(RuntimeError::NotFound(_)) => return Readiness::Crashed { exit_code: None, logs: Vec::new() },
+            Err(_) => {}
+        }
+
+        for probe in probes.iter_mut().filter(|p| !p.ready) {
+            let ok = match runtime.http_probe(container, network, probe.port, &probe.path).await {
+                Ok(status) if probe.strict => (200..300).contains(&status),
+                Ok(status) => status < 500,
+                Err(_) => false,
+            };
+            if ok {
+                probe.ready = true;
+                let _ = progress.send(format!("✓ {} ready on :{} ({}s)", probe.name, probe.port, started.elapsed().as_secs()));
+            }
+        }
+
+        let waiting_for: Vec<String> = probes.iter().filter(|p| !p.ready).map(|p| p.name.to_string()).collect();
+        if waiting_for.is_empty() {
+            return Readiness::Ready { elapsed_secs: started.elapsed().as_secs() };
+        }
+        if started.elapsed() >= timeout {
+            return Readiness::Timeout { timeout_secs: timeout.as_secs(), waiting_for };
+        }
+        if last_progress.elapsed() >= PROGRESS_EVERY {
+            last_progress = Instant::now();
+            let _ = progress.send(format!(
+                "… still waiting for {} ({}s)",
+                waiting_for.join(", "),
+                started.elapsed().as_secs()
+            ));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(state: &str, running: bool, exit_code: i64, restart_count: i64) -> ContainerDetails {
+        ContainerDetails { state: state.to_string(), running, exit_code, restart_count, ..Default::default() }
+    }
+
+    #[test]
+    fn crash_detection() {
+        let cases = [
+            // (state, running, exit code, restart count, restarts before, expected)
+            ("running", true, 0, 0, 0, None),
+            ("running", true, 0, 2, 2, None),
+            ("exited", false, 1, 0, 0, Some(1)),
+            ("dead", false, 137, 0, 0, Some(137)),
+            // Docker reports a restarting container as running
+            ("restarting", true, 3, 1, 1, Some(3)),
+            // Crashed and already restarted between two polls
+            ("running", true, 1, 3, 2, Some(1)),
+        ];
+        for (state, running, exit_code, restart_count, before, expected) in cases {
+            assert_eq!(
+                crash_exit_code(&details(state, running, exit_code, restart_count), before),
+                expected,
+                "{} running={} restarts {} -> {}",
+                state, running, before, restart_count
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_container_counts_as_crashed() {
+        let runtime = crate::runtime::FakeRuntime::default();
+        let (log, _) = tokio::sync::mpsc::unbounded_channel();
+        let missing = wait_ready(&runtime, "ghost", &AppManifest::default(), Duration::from_secs(1), &log).await;
+        assert!(matches!(missing, Readiness::Crashed { exit_code: None, .. }));
+    }
+}
@@ -0,0 +1,247 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::builds::Trigger;
+use crate::jobs::JobError;
+use crate::locks::{AppLockGuard, LockConflict, WaitQuery};
+use crate::manifest::DeployStrategy;
+use crate::replicas;
+use crate::runtime::{LogSink, RuntimeError};
+use crate::AppState;
+
+// ── Rename ──
+//
+// POST /api/apps/{app}/rename moves everything keyed by the app name: the
+// source and code-server directories, env vars and secrets (re-encrypted for
+// the new name), image revisions and the {app} image tag, auth, resource and
+// route settings, group membership and build history. Container names,
+// labels and Traefik routers can't be changed in place, so the app's
+// containers are removed first and, if it was running (or asleep), it is
+// redeployed under the new name. A failing step undoes the ones before it;
+// a failing redeploy moves everything back and redeploys the old name.
+//
+// Docker volumes can't be renamed either, and backups don't carry their
+// contents, so an app with data volumes can't be renamed.
+
+#[derive(Deserialize)]
+pub struct RenameRequest {
+    name: String,
+}
+
+// Each step moves one piece of state and works in both directions, so
+// undoing it is the same step from the new name back to the old one
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    Source,
+    CodeServer,
+    Secrets,
+    Revisions,
+    Image,
+    Settings,
+    Builds,
+}
+
+const STEPS: [Step; 7] = [
+    Step::Source,
+    Step::CodeServer,
+    Step::Secrets,
+    Step::Revisions,
+    Step::Image,
+    Step::Settings,
+    Step::Builds,
+];
+
+fn error(status: StatusCode, msg: String) -> Response {
+    (status, Json(serde_json::json!({"error": msg}))).into_response()
+}
+
+fn move_key<V>(map: &mut HashMap<String, V>, from: &str, to: &str) -> bool {
+    match map.remove(from) {
+        Some(value) => {
+            map.insert(to.to_string(), value);
+            true
+        }
+        None => false,
+    }
+}
+
+async fn run_step(state: &AppState, step: Step, from: &str, to: &str) -> Result<(), String> {
+    match step {
+        Step::Source => std::fs::rename(format!("/apps/{}", from), format!("/apps/{}", to))
+            .map_err(|e| format!("Failed to move app directory: {}", e)),
+        Step::CodeServer => {
+            let dir = crate::code_server_config_dir(from);
+            if !std::path::Path::new(&dir).exists() {
+                return Ok(());
+            }
+            std::fs::rename(&dir, crate::code_server_config_dir(to))
+                .map_err(|e| format!("Failed to move code-server directory: {}", e))
+        }
+        Step::Secrets => state.secrets.rename_app(from, to),
+        Step::Revisions => state.revisions.rename_app(state.runtime.as_ref(), from, to).await,
+        // Restarts and rebuild fallbacks use the {app} tag
+        Step::Image => match state.runtime.tag_image(from, to, "latest").await {
+            Ok(()) => {
+                let _ = state.runtime.remove_image(from).await;
+                Ok(())
+            }
+            Err(RuntimeError::NotFound(_)) => Ok(()),
+            Err(e) => Err(format!("Failed to tag {}: {}", to, e)),
+        },
+        Step::Settings => {
+            {
+                let mut config = state.auth_config.write().unwrap();
+                if move_key(&mut config, from, to) {
+                    let _ = crate::save_auth_config(&config);
+                }
+            }
+            {
+                let mut config = state.resources_config.write().unwrap();
+                if move_key(&mut config, from, to) {
+                    let _ = crate::save_resources_config(&config);
+                }
+            }
+            {
+                let mut routes = state.routes_config.write().unwrap();
+                if move_key(&mut routes, from, to) {
+                    let _ = crate::save_routes_config(&routes);
+                }
+            }
+            let mut groups = state.groups.write().unwrap();
+            let mut changed = false;
+            for name in groups.iter_mut().flat_map(|g| g.containers.iter_mut()).filter(|c| *c == from) {
+                *name = to.to_string();
+                changed = true;
+            }
+            if changed {
+                let _ = crate::save_groups(&groups);
+            }
+            Ok(())
+        }
+        Step::Builds => {
+            state.builds.rename_app(from, to);
+            Ok(())
+        }
+    }
+}
+
+// Runs every step, undoing the completed ones if one fails
+async fn move_app(state: &AppState, from: &str, to: &str, log: &LogSink) -> Result<(), String> {
+    for (i, step) in STEPS.iter().enumerate() {
+        if let Err(e) = run_step(state, *step, from, to).await {
+            let _ = log.send(format!("✗ {}", e));
+            undo(state, &STEPS[..i], from, to, log).await;
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+async fn undo(state: &AppState, steps: &[Step], from: &str, to: &str, log: &LogSink) {
+    for step in steps.iter().rev() {
+        if let Err(e) = run_step(state, *step, to, from).await {
+            let _ = log.send(format!("⚠ Failed to undo {:?}: {}", step, e));
+        }
+    }
+}
+
+async fn redeploy(state: &Arc<AppState>, app_name: &str, log: &LogSink) -> Result<Option<serde_json::Value>, JobError> {
+    let build = state.builds.start(app_name, Trigger::Api);
+    let result = crate::run_deploy(state.clone(), app_name.to_string(), DeployStrategy::Recreate, &build, build.tee(log.clone())).await;
+    build.finish(result.as_ref().map(|_| ()).map_err(|e| e.message.clone())).await;
+    result
+}
+
+pub async fn rename_app(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Query(query): Query<WaitQuery>,
+    Json(req): Json<RenameRequest>,
+) -> Result<Response, LockConflict> {
+    if !crate::valid_app_name(&app_name) || !std::path::Path::new(&format!("/apps/{}", app_name)).is_dir() {
+        return Ok(error(StatusCode::NOT_FOUND, "App not found".to_string()));
+    }
+    if !crate::valid_app_name(&req.name) {
+        return Ok(error(StatusCode::BAD_REQUEST, format!("Invalid app name '{}'", req.name)));
+    }
+    if req.name == app_name {
+        return Ok(error(StatusCode::BAD_REQUEST, "New name is the same as the current one".to_string()));
+    }
+    let guards = state.locks.lock_pair(&app_name, &req.name, "rename", query.wait).await?;
+    if std::path::Path::new(&format!("/apps/{}", req.name)).exists()
+        || std::path::Path::new(&crate::code_server_config_dir(&req.name)).exists()
+    {
+        return Ok(error(StatusCode::CONFLICT, format!("App '{}' already exists", req.name)));
+    }
+
+    let volumes = match state.runtime.list_volumes(&format!("{}={}", crate::restarts::APP_LABEL, app_name)).await {
+        Ok(v) => v,
+        Err(e) => return Ok(error(StatusCode::BAD_GATEWAY, format!("Failed to list volumes: {}", e))),
+    };
+    if !volumes.is_empty() {
+        let names: Vec<String> = volumes.into_iter().map(|v| v.name).collect();
+        return Ok(error(
+            StatusCode::CONFLICT,
+            format!(
+                "Apps with data volumes can't be renamed: Docker volumes keep their names, so {} would stay behind",
+                names.join(", ")
+            ),
+        ));
+    }
+
+    let redeploy_after = state.status.is_running(&app_name) || state.idle.is_sleeping(&app_name);
+    let job_state = state.clone();
+    let from = app_name.clone();
+    let to = req.name.clone();
+    let job_id = state.jobs.submit(&app_name, "rename", move |log| async move {
+        run_rename(job_state, from, to, redeploy_after, guards, log).await
+    });
+
+    Ok(Json(serde_json::json!({"status": "queued", "job_id": job_id})).into_response())
+}
+
+async fn run_rename(
+    state: Arc<AppState>,
+    from: String,
+    to: String,
+    redeploy_after: bool,
+    _guards: [AppLockGuard; 2],
+    log: LogSink,
+) -> Result<Option<serde_json::Value>, JobError> {
+    let _ = log.send(format!("Renaming {} to {}", from, to));
+    state.idle.forget(&state, &from);
+    let _ = replicas::remove_all(state.runtime.as_ref(), &from).await;
+    replicas::remove_green(state.runtime.as_ref(), &from).await;
+
+    move_app(&state, &from, &to, &log).await?;
+    let _ = log.send(format!("✓ Moved {} to {}", from, to));
+    if !redeploy_after {
+        return Ok(Some(serde_json::json!({ "app": to, "previous": from, "redeployed": false })));
+    }
+
+    let _ = log.send(format!("Redeploying as {}...", to));
+    match redeploy(&state, &to, &log).await {
+        Ok(readiness) => Ok(Some(serde_json::json!({
+            "app": to,
+            "previous": from,
+            "redeployed": true,
+            "readiness": readiness,
+        }))),
+        Err(e) => {
+            let _ = log.send(format!("✗ Redeploy failed; moving everything back to {}", from));
+            let _ = replicas::remove_all(state.runtime.as_ref(), &to).await;
+            undo(&state, &STEPS, &from, &to, &log).await;
+            if let Err(restore) = redeploy(&state, &from, &log).await {
+                let _ = log.send(format!("⚠ Failed to redeploy {}: {}", from, restore.message));
+            }
+            Err(JobError { message: format!("Rename failed, kept {}: {}", from, e.message), result: e.result })
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use crate::restarts::APP_LABEL;
+use crate::runtime::{ContainerRuntime, RuntimeError};
+
+// ── Replicas ──
+//
+// An app with `[scale] replicas = N` (N > 1) runs as {app}_1..{app}_N. Every
+// replica carries the app's Traefik labels, so Traefik merges them into one
+// load-balanced service, and answers to the app name on its network. With a
+// single replica the container is simply named {app}, as before.
+//
+// App names can't contain '_', so derived names ({app}_2, {app}_green) never
+// belong to another app. Containers are still only removed when their app
+// label says they are this app's.
+
+pub const REPLICA_LABEL: &str = "mcphub.replica";
+// Between the app name and a replica index or stand-in suffix
+pub const SEPARATOR: char = '_';
+
+pub fn replica_names(app_name: &str, count: u32) -> Vec<String> {
+    if count <= 1 {
+        return vec![app_name.to_string()];
+    }
+    (1..=count).map(|i| format!("{}{}{}", app_name, SEPARATOR, i)).collect()
+}
+
+// The app a container serves as one of its replicas; blue/green stand-ins
+// ({app}_green, {app}_2_green) and unrelated containers yield None
+pub fn app_of<'a>(name: &str, labels: &'a HashMap<String, String>) -> Option<&'a str> {
+    let app_name = labels.get(APP_LABEL)?;
+    if app_name == name {
+        return Some(app_name);
+    }
+    let index = labels.get(REPLICA_LABEL)?;
+    (name == format!("{}{}{}", app_name, SEPARATOR, index)).then_some(app_name.as_str())
+}
+
+// Replica index for ordering: {app} and {app}_1 come first, {app}_10 after {app}_9
+pub fn sort_key(app_name: &str, name: &str) -> u32 {
+    name.strip_prefix(app_name)
+        .and_then(|rest| rest.strip_prefix(SEPARATOR))
+        .and_then(|i| i.parse().ok())
+        .unwrap_or(0)
+}
+
+// Every container labelled with the app, including stand-ins left by an
+// interrupted rollout
+pub async fn all_containers(runtime: &dyn ContainerRuntime, app_name: &str) -> Vec<String> {
+    match runtime.list_containers(true).await {
+        Ok(containers) => containers
+            .into_iter()
+            .filter(|c| c.labels.get(APP_LABEL).is_some_and(|a| a == app_name))
+            .map(|c| c.name)
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to list containers: {}", e);
+            vec![]
+        }
+    }
+}
+
+// Removes `name` if it is one of the app's containers (or the app's own
+// container from before the label existed). Missing is fine; a container of
+// another app is refused.
+pub async fn remove_owned(runtime: &dyn ContainerRuntime, app_name: &str, name: &str) -> Result<(), String> {
+    let details = match runtime.inspect_container(name).await {
+        Ok(details) => details,
+        Err(RuntimeError::NotFound(_)) => return Ok(()),
+        Err(e) => return Err(format!("Failed to inspect {}: {}", name, e)),
+    };
+    let owned = match details.labels.get(APP_LABEL) {
+        Some(owner) => owner == app_name,
+        None => name == app_name,
+    };
+    if !owned {
+        return Err(format!("Container {} belongs to another app; refusing to remove it", name));
+    }
+    match runtime.remove_container(name).await {
+        Ok(()) | Err(RuntimeError::NotFound(_)) => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", name, e)),
+    }
+}
+
+// Removes all of the app's containers; NotFound if there were none
+pub async fn remove_all(runtime: &dyn ContainerRuntime, app_name: &str) -> Result<(), RuntimeError> {
+    let names = all_containers(runtime, app_name).await;
+    if names.is_empty() {
+        // Containers created before the app label existed
+        return runtime.remove_container(app_name).await;
+    }
+    let mut result = Ok(());
+    for name in names {
+        if let Err(e) = runtime.remove_container(&name).await {
+            result = Err(e);
+        }
+    }
+    result
+}
+
+// Removes blue/green stand-ins for every replica
+pub async fn remove_green(runtime: &dyn ContainerRuntime, app_name: &str) {
+    for name in all_containers(runtime, app_name).await {
+        if name.ends_with(&crate::rollout::green_name("")) {
+            let _ = runtime.remove_container(&name).await;
+        }
+    }
+}
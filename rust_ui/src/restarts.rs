@@ -0,0 +1,167 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::manifest::RestartPolicy;
+use crate::now_iso8601;
+use crate::runtime::{ContainerRuntime, RestartSpec};
+
+// ── Restart policies and crash-loop detection ──
+//
+// Docker restarts containers according to the app's policy; a background
+// watcher samples each app container's RestartCount and flags the app as
+// CrashLoop once it restarts CRASHLOOP_THRESHOLD times within the window,
+// or when it has stopped with a failure after that many restarts. Replicas
+// are tracked separately under their container names.
+
+pub const APP_LABEL: &str = "mcphub.app";
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+const CRASHLOOP_WINDOW: Duration = Duration::from_secs(300);
+const DEFAULT_CRASHLOOP_THRESHOLD: usize = 3;
+
+pub fn restart_spec(policy: RestartPolicy) -> RestartSpec {
+    match policy {
+        RestartPolicy::Never => RestartSpec { name: "no".into(), max_retries: 0 },
+        RestartPolicy::OnFailure { max_retries } => RestartSpec {
+            name: "on-failure".into(),
+            max_retries: max_retries.unwrap_or(0),
+        },
+        RestartPolicy::Always => RestartSpec { name: "always".into(), max_retries: 0 },
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashStatus {
+    pub crash_loop: bool,
+    pub crash_loop_since: Option<String>,
+    pub running: bool,
+    pub restart_count: i64,
+    pub exit_code: i64,
+    // Restarts seen within the detection window
+    pub recent_restarts: usize,
+}
+
+impl CrashStatus {
+    // Crash-looping, or stopped with a non-zero exit code
+    pub fn is_error(&self) -> bool {
+        self.crash_loop || (!self.running && self.exit_code != 0)
+    }
+
+    // Status shown instead of Docker's own while crash-looping or stopped
+    pub fn label(&self) -> Option<String> {
+        if self.crash_loop {
+            Some(format!("CrashLoop ({} restarts)", self.restart_count))
+        } else if !self.running {
+            Some(format!("Exited ({})", self.exit_code))
+        } else {
+            None
+        }
+    }
+}
+
+struct Tracked {
+    container_id: String,
+    restarts: VecDeque<Instant>,
+    status: CrashStatus,
+}
+
+#[derive(Clone)]
+pub struct CrashWatcher {
+    apps: Arc<RwLock<HashMap<String, Tracked>>>,
+    threshold: usize,
+}
+
+impl CrashWatcher {
+    pub fn from_env() -> Self {
+        let threshold = std::env::var("CRASHLOOP_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|t| *t >= 1)
+            .unwrap_or(DEFAULT_CRASHLOOP_THRESHOLD);
+        CrashWatcher { apps: Arc::new(RwLock::new(HashMap::new())), threshold }
+    }
+
+    pub fn status(&self, app_name: &str) -> Option<CrashStatus> {
+        self.apps.read().unwrap().get(app_name).map(|t| t.status.clone())
+    }
+
+    pub fn spawn(&self, runtime: Arc<dyn ContainerRuntime>) {
+        let watcher = self.clone();
+        tokio::spawn(async move {
+            loop {
+                watcher.sample(runtime.as_ref()).await;
+                tokio::time::sleep(WATCH_INTERVAL).await;
+            }
+        });
+    }
+
+    async fn sample(&self, runtime: &dyn ContainerRuntime) {
+        let containers = match runtime.list_containers(true).await {
+            Ok(c) => c,
+            Err(_) => return,
+        };
+        let mut seen = Vec::new();
+        for c in containers {
+            // Skips {app}_green while a blue/green rollout is in progress
+            if crate::replicas::app_of(&c.name, &c.labels).is_none() {
+                continue;
+            }
+            let app_name = c.name;
+            let Ok(details) = runtime.inspect_container(&app_name).await else { continue };
+            seen.push(app_name.clone());
+
+            let mut apps = self.apps.write().unwrap();
+            let tracked = apps.entry(app_name.clone()).or_insert_with(|| Tracked {
+                container_id: String::new(),
+                restarts: VecDeque::new(),
+                status: CrashStatus {
+                    crash_loop: false,
+                    crash_loop_since: None,
+                    running: details.running,
+                    restart_count: details.restart_count,
+                    exit_code: details.exit_code,
+                    recent_restarts: 0,
+                },
+            });
+            if tracked.container_id != details.id {
+                // New container: start counting from its current restart count
+                tracked.container_id = details.id.clone();
+                tracked.restarts.clear();
+                tracked.status.restart_count = details.restart_count;
+                tracked.status.crash_loop = false;
+                tracked.status.crash_loop_since = None;
+            }
+
+            let now = Instant::now();
+            let new_restarts = (details.restart_count - tracked.status.restart_count).max(0);
+            for _ in 0..new_restarts {
+                tracked.restarts.push_back(now);
+            }
+            while tracked.restarts.front().is_some_and(|t| now.duration_since(*t) > CRASHLOOP_WINDOW) {
+                tracked.restarts.pop_front();
+            }
+
+            let status = &mut tracked.status;
+            status.running = details.running;
+            status.restart_count = details.restart_count;
+            status.exit_code = details.exit_code;
+            status.recent_restarts = tracked.restarts.len();
+
+            let gave_up = !details.running
+                && details.exit_code != 0
+                && details.restart_count >= self.threshold as i64;
+            let crash_loop = tracked.restarts.len() >= self.threshold || gave_up;
+            if crash_loop && !status.crash_loop {
+                eprintln!("App {} is crash-looping ({} restarts, exit code {})", app_name, details.restart_count, details.exit_code);
+                status.crash_loop_since = Some(now_iso8601());
+            } else if !crash_loop {
+                status.crash_loop_since = None;
+            }
+            status.crash_loop = crash_loop;
+        }
+
+        // Containers that no longer exist have nothing left to report
+        self.apps.write().unwrap().retain(|name, _| seen.contains(name));
+    }
+}
@@ -0,0 +1,277 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::jobs::JobError;
+use crate::locks::{LockConflict, WaitQuery};
+use crate::manifest::{self, DeployStrategy};
+use crate::rollout::{self, DeployOptions};
+use crate::runtime::{ContainerRuntime, LogSink};
+use crate::{now_iso8601, AppState};
+
+// ── Image revisions ──
+//
+// Every successful build is also tagged {app}:r{N} with N increasing per app
+// (never reused, even after pruning). The newest MAX_IMAGE_REVISIONS tags are
+// kept; {app}:latest always points at the image currently deployed.
+
+const REVISIONS_PATH: &str = "/apps/revisions.json";
+const DEFAULT_MAX_REVISIONS: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub rev: u64,
+    pub image: String,
+    pub image_id: String,
+    // "deploy" or "rebuild"
+    pub source: String,
+    pub created_at: String,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct AppRevisions {
+    next: u64,
+    revisions: Vec<Revision>,
+}
+
+#[derive(Clone)]
+pub struct RevisionStore {
+    apps: Arc<RwLock<HashMap<String, AppRevisions>>>,
+    max_revisions: usize,
+}
+
+fn save_revisions(apps: &HashMap<String, AppRevisions>) -> Result<(), std::io::Error> {
+    let json = serde_json::to_string_pretty(apps).map_err(std::io::Error::other)?;
+    std::fs::write(REVISIONS_PATH, json)
+}
+
+pub fn revision_tag(rev: u64) -> String {
+    format!("r{}", rev)
+}
+
+impl RevisionStore {
+    pub fn from_env() -> Self {
+        let apps = std::fs::read_to_string(REVISIONS_PATH)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        let max_revisions = std::env::var("MAX_IMAGE_REVISIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n >= 1)
+            .unwrap_or(DEFAULT_MAX_REVISIONS);
+        RevisionStore { apps: Arc::new(RwLock::new(apps)), max_revisions }
+    }
+
+    pub fn list(&self, app_name: &str) -> Vec<Revision> {
+        let apps = self.apps.read().unwrap();
+        let mut list = apps.get(app_name).map(|a| a.revisions.clone()).unwrap_or_default();
+        list.reverse();
+        list
+    }
+
+    pub fn get(&self, app_name: &str, rev: u64) -> Option<Revision> {
+        let apps = self.apps.read().unwrap();
+        apps.get(app_name)?.revisions.iter().find(|r| r.rev == rev).cloned()
+    }
+
+    // Tags a freshly built image as the app's next revision and prunes old ones
+    pub async fn record(
+        &self,
+        runtime: &dyn ContainerRuntime,
+        app_name: &str,
+        image_id: &str,
+        source: &str,
+    ) -> Result<Revision, String> {
+        let rev = {
+            let mut apps = self.apps.write().unwrap();
+            let entry = apps.entry(app_name.to_string()).or_default();
+            entry.next += 1;
+            entry.next
+        };
+        let tag = revision_tag(rev);
+        runtime
+            .tag_image(image_id, app_name, &tag)
+            .await
+            .map_err(|e| format!("Failed to tag {}:{}: {}", app_name, tag, e))?;
+
+        let revision = Revision {
+            rev,
+            image: format!("{}:{}", app_name, tag),
+            image_id: image_id.to_string(),
+            source: source.to_string(),
+            created_at: now_iso8601(),
+        };
+        let pruned: Vec<Revision> = {
+            let mut apps = self.apps.write().unwrap();
+            let entry = apps.entry(app_name.to_string()).or_default();
+            entry.revisions.push(revision.clone());
+            let excess = entry.revisions.len().saturating_sub(self.max_revisions);
+            let pruned = entry.revisions.drain(..excess).collect();
+            let _ = save_revisions(&apps);
+            pruned
+        };
+        for old in pruned {
+            // Untagging only; the image stays if a container still uses it
+            let _ = runtime.remove_image(&old.image).await;
+        }
+        Ok(revision)
+    }
+
+    pub async fn remove_app(&self, runtime: &dyn ContainerRuntime, app_name: &str) {
+        let removed = {
+            let mut apps = self.apps.write().unwrap();
+            let removed = apps.remove(app_name);
+            if removed.is_some() {
+                let _ = save_revisions(&apps);
+            }
+            removed
+        };
+        for revision in removed.map(|a| a.revisions).unwrap_or_default() {
+            let _ = runtime.remove_image(&revision.image).await;
+        }
+    }
+
+    // Re-tags a renamed app's revisions as {to}:r{N}, keeping their numbers;
+    // the old tags are only removed once every new one is in place
+    pub async fn rename_app(&self, runtime: &dyn ContainerRuntime, from: &str, to: &str) -> Result<(), String> {
+        let Some(mut entry) = self.apps.read().unwrap().get(from).cloned() else { return Ok(()) };
+        let mut tagged: Vec<String> = Vec::new();
+        for revision in &entry.revisions {
+            let tag = revision_tag(revision.rev);
+            if let Err(e) = runtime.tag_image(&revision.image_id, to, &tag).await {
+                for image in tagged {
+                    let _ = runtime.remove_image(&image).await;
+                }
+                return Err(format!("Failed to tag {}:{}: {}", to, tag, e));
+            }
+            tagged.push(format!("{}:{}", to, tag));
+        }
+        for (revision, image) in entry.revisions.iter_mut().zip(tagged) {
+            let _ = runtime.remove_image(&revision.image).await;
+            revision.image = image;
+        }
+        let mut apps = self.apps.write().unwrap();
+        apps.remove(from);
+        apps.insert(to.to_string(), entry);
+        let _ = save_revisions(&apps);
+        Ok(())
+    }
+}
+
+// Records the build as a new revision and reports it on `log`
+pub async fn record_build(state: &AppState, app_name: &str, image_id: &str, source: &str, log: &LogSink) {
+    match state.revisions.record(state.runtime.as_ref(), app_name, image_id, source).await {
+        Ok(revision) => {
+            let _ = log.send(format!("✓ Tagged {}", revision.image));
+        }
+        Err(e) => {
+            let _ = log.send(format!("⚠ {}", e));
+        }
+    }
+}
+
+// ── Handlers ──
+
+#[derive(Serialize)]
+struct RevisionView {
+    #[serde(flatten)]
+    revision: Revision,
+    // Whether the app's container currently runs this image
+    current: bool,
+}
+
+pub async fn list_revisions(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> Json<serde_json::Value> {
+    let first_replica = state.status.replica_names(&app_name).remove(0);
+    let current_image = state.runtime.inspect_container(&first_replica).await.ok().map(|c| c.image);
+    let revisions: Vec<RevisionView> = state.revisions.list(&app_name)
+        .into_iter()
+        .map(|revision| RevisionView {
+            current: current_image.as_deref() == Some(revision.image_id.as_str()),
+            revision,
+        })
+        .collect();
+    Json(serde_json::json!({ "app": app_name, "revisions": revisions }))
+}
+
+pub async fn rollback(
+    State(state): State<Arc<AppState>>,
+    Path((app_name, rev)): Path<(String, String)>,
+    Query(query): Query<WaitQuery>,
+    Query(options): Query<DeployOptions>,
+) -> Result<Response, LockConflict> {
+    let not_found = |msg: &str| {
+        Ok((StatusCode::NOT_FOUND, Json(serde_json::json!({"error": msg}))).into_response())
+    };
+    if !std::path::Path::new(&format!("/apps/{}", app_name)).exists() {
+        return not_found("App directory not found");
+    }
+    // Accept both "3" and "r3"
+    let Some(revision) = rev.trim_start_matches('r').parse().ok().and_then(|n| state.revisions.get(&app_name, n)) else {
+        return not_found(&format!("Revision {} not found for {}", rev, app_name));
+    };
+
+    let guard = if query.wait {
+        None
+    } else {
+        Some(state.locks.try_acquire(&app_name, "rollback")?)
+    };
+    let strategy = options.strategy
+        .or_else(|| manifest::load(&app_name).ok().map(|m| m.deploy_strategy))
+        .unwrap_or_default();
+    let kind = rollout::job_kind(strategy, "rollback");
+
+    let job_state = state.clone();
+    let job_app = app_name.clone();
+    let job_id = state.jobs.submit(&app_name, &kind, move |log| async move {
+        let _guard = match guard {
+            Some(g) => g,
+            None => {
+                let _ = log.send(format!("Waiting for other operations on {} to finish...", job_app));
+                job_state.locks.acquire(&job_app, "rollback").await
+            }
+        };
+        run_rollback(job_state, job_app, revision, strategy, log).await
+    });
+
+    Ok(Json(serde_json::json!({"status": "queued", "job_id": job_id})).into_response())
+}
+
+async fn run_rollback(
+    state: Arc<AppState>,
+    app_name: String,
+    revision: Revision,
+    strategy: DeployStrategy,
+    log: LogSink,
+) -> Result<Option<serde_json::Value>, JobError> {
+    let manifest = manifest::load(&app_name).map_err(|errors| crate::manifest_error_text(&errors))?;
+    // Same labels, mounts and limits as a deploy, just the older image
+    let mut spec = crate::app_run_spec(&state, &app_name, &manifest)?;
+    spec.image = revision.image.clone();
+    let _ = log.send(format!("Rolling back {} to {} ({})", app_name, revision.image, revision.created_at));
+
+    crate::ensure_code_server_host_dirs(&app_name);
+    let readiness = rollout::roll_out(
+        state.runtime.as_ref(), &app_name, &manifest, spec, strategy, state.readiness_timeout, &log, &log,
+    ).await?;
+    let result = serde_json::json!({ "rev": revision.rev, "readiness": readiness });
+    if !readiness.is_ready() {
+        return Err(JobError { message: readiness.summary(), result: Some(result) });
+    }
+
+    // Later restarts and rebuild fallbacks use {app}:latest
+    if let Err(e) = state.runtime.tag_image(&revision.image_id, &app_name, "latest").await {
+        let _ = log.send(format!("⚠ Failed to retag {}:latest: {}", app_name, e));
+    }
+    let _ = log.send(format!("✓ {} is now running {}", app_name, revision.image));
+    Ok(Some(result))
+}
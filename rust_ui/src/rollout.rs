@@ -0,0 +1,257 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::manifest::{AppManifest, DeployStrategy};
+use crate::readiness::{self, Readiness};
+use crate::replicas::{self, REPLICA_LABEL};
+use crate::runtime::{ContainerRuntime, LogSink, RunSpec};
+
+// ── Container rollout ──
+//
+// Recreate removes {app} and starts the new container in its place.
+// Blue/green starts {app}_green with the same labels (so Traefik adds it to
+// the same services), waits for readiness, then renames it to {app} and
+// removes the old container. If the new container never becomes ready it is
+// removed and the old one keeps serving.
+//
+// Apps with several replicas are rolled one replica at a time with the same
+// strategy, so the others keep serving; the rollout stops at the first
+// replica that does not become ready. Containers left over from a different
+// replica count are removed once every replica is ready.
+
+// `?strategy=blue-green` overrides the manifest's [deploy] strategy
+#[derive(Deserialize, Default)]
+pub struct DeployOptions {
+    pub strategy: Option<DeployStrategy>,
+}
+
+// Job kind shown for a deploy-like operation: "deploy", "blue-green deploy", ...
+pub fn job_kind(strategy: DeployStrategy, operation: &str) -> String {
+    match strategy {
+        DeployStrategy::Recreate => operation.to_string(),
+        DeployStrategy::BlueGreen => format!("blue-green {}", operation),
+    }
+}
+
+pub fn green_name(app_name: &str) -> String {
+    format!("{}{}green", app_name, replicas::SEPARATOR)
+}
+
+fn blue_name(app_name: &str) -> String {
+    format!("{}{}blue", app_name, replicas::SEPARATOR)
+}
+
+// Starts `spec` for the app and waits for readiness. Container lifecycle
+// messages go to `log`, readiness progress to `progress`.
+#[allow(clippy::too_many_arguments)]
+pub async fn roll_out(
+    runtime: &dyn ContainerRuntime,
+    app_name: &str,
+    manifest: &AppManifest,
+    spec: RunSpec,
+    strategy: DeployStrategy,
+    timeout: Duration,
+    log: &LogSink,
+    progress: &LogSink,
+) -> Result<Readiness, String> {
+    crate::volumes::ensure(runtime, app_name, manifest, log).await?;
+    let names = replicas::replica_names(app_name, manifest.replicas);
+    let mut state = None;
+    for (i, name) in names.iter().enumerate() {
+        let mut replica = spec.clone();
+        if names.len() > 1 {
+            let _ = log.send(format!("Replica {}/{}: {}", i + 1, names.len(), name));
+            replica.name = name.clone();
+            replica.labels.insert(REPLICA_LABEL.to_string(), (i + 1).to_string());
+            replica.network_aliases.push(app_name.to_string());
+        }
+        let replica_state = roll_out_container(runtime, app_name, name, manifest, replica, strategy, timeout, log, progress).await?;
+        if !replica_state.is_ready() {
+            return Ok(replica_state);
+        }
+        // A later cancel leaves replicas that already made it alone
+        crate::jobs::record_settled(name);
+        crate::jobs::record_settled(&green_name(name));
+        state = Some(replica_state);
+    }
+
+    for stale in replicas::all_containers(runtime, app_name).await {
+        if !names.contains(&stale) {
+            let _ = runtime.remove_container(&stale).await;
+            let _ = log.send(format!("✓ Removed {} (no longer a replica)", stale));
+        }
+    }
+    state.ok_or_else(|| "No replicas to start".to_string())
+}
+
+// One container named `name`: recreate it in place or swap it blue/green
+#[allow(clippy::too_many_arguments)]
+async fn roll_out_container(
+    runtime: &dyn ContainerRuntime,
+    app_name: &str,
+    name: &str,
+    manifest: &AppManifest,
+    mut spec: RunSpec,
+    strategy: DeployStrategy,
+    timeout: Duration,
+    log: &LogSink,
+    progress: &LogSink,
+) -> Result<Readiness, String> {
+    let old_running = runtime.inspect_container(name).await.is_ok_and(|c| c.running);
+    if strategy == DeployStrategy::Recreate || !old_running {
+        if strategy == DeployStrategy::BlueGreen {
+            let _ = log.send("No running container to swap with; starting directly".to_string());
+        }
+        // Stop the existing container, if there is one
+        replicas::remove_owned(runtime, app_name, name).await?;
+        crate::jobs::record_started(name);
+        runtime.run_container(&spec).await.map_err(|e| format!("Container start failed: {}", e))?;
+        let _ = log.send("✓ Container started successfully".to_string());
+        return Ok(readiness::wait_ready(runtime, name, manifest, timeout, progress).await);
+    }
+
+    // Leftover from an interrupted rollout
+    let green = green_name(name);
+    replicas::remove_owned(runtime, app_name, &green).await?;
+
+    spec.name = green.clone();
+    crate::jobs::record_started(&green);
+    runtime.run_container(&spec).await.map_err(|e| format!("Container start failed: {}", e))?;
+    let _ = log.send(format!("✓ Started {} next to the running container", green));
+
+    let state = readiness::wait_ready(runtime, &green, manifest, timeout, progress).await;
+    if !state.is_ready() {
+        let _ = runtime.remove_container(&green).await;
+        let _ = log.send(format!("✗ {} did not become ready; the previous container keeps serving", green));
+        return Ok(state);
+    }
+
+    // Swap names so everything addressing the container by app name sees the new one
+    let blue = blue_name(name);
+    if let Err(e) = replicas::remove_owned(runtime, app_name, &blue).await {
+        let _ = runtime.remove_container(&green).await;
+        return Err(e);
+    }
+    if let Err(e) = runtime.rename_container(name, &blue).await {
+        let _ = runtime.remove_container(&green).await;
+        return Err(format!("Failed to swap containers: {}", e));
+    }
+    if let Err(e) = runtime.rename_container(&green, name).await {
+        let _ = runtime.rename_container(&blue, name).await;
+        let _ = runtime.remove_container(&green).await;
+        return Err(format!("Failed to swap containers: {}", e));
+    }
+    let _ = runtime.remove_container(&blue).await;
+    let _ = log.send("✓ Switched to the new container and removed the old one".to_string());
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restarts::APP_LABEL;
+    use crate::runtime::{BuildSpec, FakeRuntime};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    // A fake runtime with `image` built, and a spec for the app's container
+    async fn setup(app_name: &str, image: &str) -> (FakeRuntime, RunSpec) {
+        let context = std::env::temp_dir().join(format!("rollout-test-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::SeqCst)));
+        std::fs::create_dir_all(&context).unwrap();
+        std::fs::write(context.join("Dockerfile"), "FROM scratch\n").unwrap();
+        let runtime = FakeRuntime::default();
+        let (sink, _) = tokio::sync::mpsc::unbounded_channel();
+        let spec = BuildSpec { tag: image.to_string(), context_dir: context.display().to_string(), network_mode: None };
+        runtime.build_image(&spec, &sink).await.unwrap();
+        let _ = std::fs::remove_dir_all(&context);
+
+        let mut run = RunSpec::new(app_name, image);
+        run.labels.insert(APP_LABEL.to_string(), app_name.to_string());
+        (runtime, run)
+    }
+
+    async fn deploy(runtime: &FakeRuntime, app_name: &str, manifest: &AppManifest, spec: RunSpec, strategy: DeployStrategy) -> Readiness {
+        let (log, _) = tokio::sync::mpsc::unbounded_channel();
+        roll_out(runtime, app_name, manifest, spec, strategy, TIMEOUT, &log, &log).await.unwrap()
+    }
+
+    async fn names(runtime: &FakeRuntime) -> Vec<String> {
+        runtime.list_containers(true).await.unwrap().into_iter().map(|c| c.name).collect()
+    }
+
+    #[tokio::test]
+    async fn recreate_replaces_the_container() {
+        let (runtime, spec) = setup("demo", "demo:latest").await;
+        let manifest = AppManifest::default();
+        assert!(deploy(&runtime, "demo", &manifest, spec.clone(), DeployStrategy::Recreate).await.is_ready());
+        let first = runtime.inspect_container("demo").await.unwrap().id;
+
+        assert!(deploy(&runtime, "demo", &manifest, spec, DeployStrategy::Recreate).await.is_ready());
+        let second = runtime.inspect_container("demo").await.unwrap();
+        assert_ne!(second.id, first);
+        assert!(second.running);
+        assert_eq!(names(&runtime).await, vec!["demo".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn blue_green_swaps_in_the_new_container() {
+        let (runtime, spec) = setup("demo", "demo:latest").await;
+        let manifest = AppManifest::default();
+        // Nothing to swap with yet, so the first deploy starts directly
+        assert!(deploy(&runtime, "demo", &manifest, spec.clone(), DeployStrategy::BlueGreen).await.is_ready());
+        let first = runtime.inspect_container("demo").await.unwrap().id;
+
+        assert!(deploy(&runtime, "demo", &manifest, spec, DeployStrategy::BlueGreen).await.is_ready());
+        assert_ne!(runtime.inspect_container("demo").await.unwrap().id, first);
+        assert_eq!(names(&runtime).await, vec!["demo".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn replicas_are_rolled_and_leftovers_removed() {
+        let (runtime, spec) = setup("demo", "demo:latest").await;
+        assert!(deploy(&runtime, "demo", &AppManifest::default(), spec.clone(), DeployStrategy::Recreate).await.is_ready());
+
+        let manifest = AppManifest { replicas: 2, ..Default::default() };
+        assert!(deploy(&runtime, "demo", &manifest, spec, DeployStrategy::Recreate).await.is_ready());
+        assert_eq!(names(&runtime).await, vec!["demo_1".to_string(), "demo_2".to_string()]);
+        let second = runtime.inspect_container("demo_2").await.unwrap();
+        assert_eq!(second.labels.get(REPLICA_LABEL).map(String::as_str), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn containers_of_other_apps_are_left_alone() {
+        let (runtime, spec) = setup("demo", "demo:latest").await;
+        // An app named like one of demo's old-style replicas
+        let mut other = RunSpec::new("demo-2", "demo:latest");
+        other.labels.insert(APP_LABEL.to_string(), "demo-2".to_string());
+        runtime.run_container(&other).await.unwrap();
+        let manifest = AppManifest { replicas: 2, ..Default::default() };
+        assert!(deploy(&runtime, "demo", &manifest, spec.clone(), DeployStrategy::Recreate).await.is_ready());
+        assert_eq!(names(&runtime).await, vec!["demo-2".to_string(), "demo_1".to_string(), "demo_2".to_string()]);
+
+        // A container under a derived name that isn't demo's is never removed
+        let (runtime, spec) = setup("demo", "demo:latest").await;
+        let mut squatter = RunSpec::new("demo_green", "demo:latest");
+        squatter.labels.insert(APP_LABEL.to_string(), "elsewhere".to_string());
+        runtime.run_container(&squatter).await.unwrap();
+        let (log, _) = tokio::sync::mpsc::unbounded_channel();
+        let manifest = AppManifest::default();
+        roll_out(&runtime, "demo", &manifest, spec.clone(), DeployStrategy::Recreate, TIMEOUT, &log, &log).await.unwrap();
+        let result = roll_out(&runtime, "demo", &manifest, spec, DeployStrategy::BlueGreen, TIMEOUT, &log, &log).await;
+        assert!(result.unwrap_err().contains("belongs to another app"));
+        assert!(runtime.inspect_container("demo_green").await.is_ok());
+        assert!(runtime.inspect_container("demo").await.unwrap().running);
+    }
+
+    #[tokio::test]
+    async fn missing_image_fails_the_rollout() {
+        let (runtime, _) = setup("demo", "demo:latest").await;
+        let (log, _) = tokio::sync::mpsc::unbounded_channel();
+        let spec = RunSpec::new("demo", "other:latest");
+        let result = roll_out(&runtime, "demo", &AppManifest::default(), spec, DeployStrategy::Recreate, TIMEOUT, &log, &log).await;
+        assert!(result.unwrap_err().starts_with("Container start failed"));
+        assert!(names(&runtime).await.is_empty());
+    }
+}
@@ -0,0 +1,1511 @@
+use async_trait::async_trait;
+use axum::body::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::sync::mpsc::UnboundedSender;
+
+// ── Runtime types ──
+
+pub type LogSink = UnboundedSender<String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    pub fn label(self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+        }
+    }
+}
+
+pub type LogLineSink = UnboundedSender<(LogStream, String)>;
+
+pub trait ExecIo: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> ExecIo for T {}
+
+// Container lifecycle event (`docker events`), e.g. start, die, oom,
+// "health_status: healthy", destroy, rename
+#[derive(Debug, Clone)]
+pub struct ContainerEvent {
+    pub action: String,
+    pub name: String,
+    // Previous name for rename events
+    pub old_name: Option<String>,
+}
+
+// One resource usage sample; network counters are totals since the container started
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContainerStats {
+    pub cpu_percent: f64,
+    pub memory_bytes: u64,
+    pub memory_limit: u64,
+    pub net_rx_bytes: u64,
+    pub net_tx_bytes: u64,
+    pub pids: u64,
+}
+
+// Interactive process with a TTY; writes go to its stdin, reads return its output
+pub struct ExecSession {
+    pub id: String,
+    pub io: Box<dyn ExecIo>,
+}
+
+// `docker logs` options for stream_logs
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    // Last N lines; None for the whole log
+    pub tail: Option<usize>,
+    // Unix timestamps
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub stdout: bool,
+    pub stderr: bool,
+    pub timestamps: bool,
+    pub follow: bool,
+}
+
+#[derive(Debug)]
+pub enum RuntimeError {
+    NotFound(String),
+    Conflict(String),
+    Api { status: u16, message: String },
+    Build(String),
+    Io(String),
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::NotFound(m) => write!(f, "not found: {}", m),
+            RuntimeError::Conflict(m) => write!(f, "conflict: {}", m),
+            RuntimeError::Api { status, message } => write!(f, "docker API error ({}): {}", status, message),
+            RuntimeError::Build(m) => write!(f, "build failed: {}", m),
+            RuntimeError::Io(m) => write!(f, "docker connection error: {}", m),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContainerInfo {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub state: String,
+    pub status: String,
+    pub labels: HashMap<String, String>,
+}
+
+impl ContainerInfo {
+    pub fn is_running(&self) -> bool {
+        self.state == "running"
+    }
+}
+
+// Subset of `docker inspect` used for readiness and status reporting
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContainerDetails {
+    pub id: String,
+    pub name: String,
+    // Image ID the container was created from
+    pub image: String,
+    pub state: String,
+    pub running: bool,
+    pub exit_code: i64,
+    pub restart_count: i64,
+    pub started_at: String,
+    pub finished_at: String,
+    // Docker healthcheck status ("starting", "healthy", "unhealthy") if one is configured
+    pub health: Option<String>,
+    // Network name -> container IP
+    pub ip_addresses: BTreeMap<String, String>,
+    pub labels: HashMap<String, String>,
+}
+
+// Named volume (`docker volume inspect`)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VolumeInfo {
+    pub name: String,
+    pub driver: String,
+    // Path on the Docker host
+    pub mountpoint: String,
+    pub created_at: String,
+    pub labels: HashMap<String, String>,
+}
+
+// Disk usage of a volume (`docker system df -v`)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VolumeUsage {
+    // None when the driver can't tell
+    pub size_bytes: Option<u64>,
+    // Containers using the volume
+    pub ref_count: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BuildSpec {
+    pub tag: String,
+    pub context_dir: String,
+    pub network_mode: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthcheckSpec {
+    // Docker healthcheck test, e.g. ["CMD-SHELL", "curl -fsS http://localhost/ || exit 1"]
+    pub test: Vec<String>,
+    pub interval_secs: u64,
+    pub timeout_secs: u64,
+    pub retries: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct RestartSpec {
+    // Docker policy name: "no", "on-failure" or "always"
+    pub name: String,
+    // Only used with "on-failure"; 0 means unlimited
+    pub max_retries: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RunSpec {
+    pub name: String,
+    pub image: String,
+    pub network: Option<String>,
+    // Extra DNS names on `network` (replicas all answer to the app name)
+    pub network_aliases: Vec<String>,
+    pub env: Vec<String>,
+    pub binds: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+    pub memory_bytes: Option<i64>,
+    pub nano_cpus: Option<i64>,
+    pub pids_limit: Option<i64>,
+    pub healthcheck: Option<HealthcheckSpec>,
+    pub restart: Option<RestartSpec>,
+}
+
+impl RunSpec {
+    pub fn new(name: &str, image: &str) -> Self {
+        RunSpec { name: name.to_string(), image: image.to_string(), ..Default::default() }
+    }
+}
+
+#[async_trait]
+pub trait ContainerRuntime: Send + Sync {
+    // Containers known to the engine; `all` includes stopped ones (like `docker ps -a`)
+    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, RuntimeError>;
+
+    // Builds `spec.context_dir` and tags it; build output is forwarded line by line.
+    // Returns the resulting image ID.
+    async fn build_image(&self, spec: &BuildSpec, output: &LogSink) -> Result<String, RuntimeError>;
+
+    // Adds `repo:tag` to an existing image (name or ID)
+    async fn tag_image(&self, image: &str, repo: &str, tag: &str) -> Result<(), RuntimeError>;
+
+    // Removes an image reference; the image itself goes once nothing else uses it
+    async fn remove_image(&self, image: &str) -> Result<(), RuntimeError>;
+
+    // Creates and starts a container, returning its ID
+    async fn run_container(&self, spec: &RunSpec) -> Result<String, RuntimeError>;
+
+    // Force-removes a container (stopping it first if needed)
+    async fn remove_container(&self, name: &str) -> Result<(), RuntimeError>;
+
+    async fn container_logs(&self, name: &str, tail: usize) -> Result<String, RuntimeError>;
+
+    // Sends log lines as they are read. With `follow`, keeps going until the
+    // container stops or `output` is closed.
+    async fn stream_logs(&self, name: &str, options: &LogOptions, output: &LogLineSink) -> Result<(), RuntimeError>;
+
+    async fn inspect_container(&self, name: &str) -> Result<ContainerDetails, RuntimeError>;
+
+    async fn container_stats(&self, name: &str) -> Result<ContainerStats, RuntimeError>;
+
+    // Sends container events as they happen until the stream drops or `output` is closed
+    async fn watch_events(&self, output: &UnboundedSender<ContainerEvent>) -> Result<(), RuntimeError>;
+
+    async fn rename_container(&self, name: &str, new_name: &str) -> Result<(), RuntimeError>;
+
+    // GETs `path` on a port of the container (over `network` if given) and
+    // returns the HTTP status; connection failures are errors
+    async fn http_probe(&self, name: &str, network: Option<&str>, port: u16, path: &str) -> Result<u16, RuntimeError>;
+
+    // Runs a command inside a container; when `detach` is false, waits for it to exit
+    async fn exec(&self, name: &str, cmd: &[String], detach: bool) -> Result<(), RuntimeError>;
+
+    // Starts a command with a TTY of `cols` x `rows` and attaches to it
+    async fn exec_interactive(&self, name: &str, cmd: &[String], cols: u16, rows: u16) -> Result<ExecSession, RuntimeError>;
+
+    async fn resize_exec(&self, exec_id: &str, cols: u16, rows: u16) -> Result<(), RuntimeError>;
+
+    // Creates a named local volume; an existing volume of that name is returned as is
+    async fn create_volume(&self, name: &str, labels: &BTreeMap<String, String>) -> Result<VolumeInfo, RuntimeError>;
+
+    // Volumes carrying `label` ("key=value")
+    async fn list_volumes(&self, label: &str) -> Result<Vec<VolumeInfo>, RuntimeError>;
+
+    async fn inspect_volume(&self, name: &str) -> Result<VolumeInfo, RuntimeError>;
+
+    // Usage of every volume by name. Docker walks each volume for this, so it is slow.
+    async fn volume_usage(&self) -> Result<HashMap<String, VolumeUsage>, RuntimeError>;
+
+    // Fails with Conflict while a container still uses the volume
+    async fn remove_volume(&self, name: &str) -> Result<(), RuntimeError>;
+}
+
+pub fn runtime_from_env() -> std::sync::Arc<dyn ContainerRuntime> {
+    match std::env::var("MCPHUB_RUNTIME").as_deref() {
+        Ok("fake") => {
+            println!("Using in-memory fake container runtime");
+            std::sync::Arc::new(FakeRuntime::default())
+        }
+        _ => {
+            let engine = DockerEngine::from_env();
+            println!("Using Docker Engine API at {}", engine.socket_path);
+            std::sync::Arc::new(engine)
+        }
+    }
+}
+
+// ── Docker Engine API over the unix socket ──
+
+const DEFAULT_DOCKER_SOCKET: &str = "/var/run/docker.sock";
+
+pub struct DockerEngine {
+    socket_path: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiContainer {
+    id: String,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    labels: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiContainerInspect {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    restart_count: i64,
+    state: ApiContainerState,
+    #[serde(default)]
+    config: Option<ApiContainerConfig>,
+    #[serde(default)]
+    network_settings: Option<ApiNetworkSettings>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiContainerConfig {
+    #[serde(default)]
+    labels: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiContainerState {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    running: bool,
+    #[serde(default)]
+    exit_code: i64,
+    #[serde(default)]
+    started_at: String,
+    #[serde(default)]
+    finished_at: String,
+    #[serde(default)]
+    health: Option<ApiHealth>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiHealth {
+    status: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiNetworkSettings {
+    #[serde(default)]
+    networks: Option<HashMap<String, ApiEndpoint>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiEndpoint {
+    #[serde(rename = "IPAddress", default)]
+    ip_address: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiVolume {
+    name: String,
+    #[serde(default)]
+    driver: String,
+    #[serde(default)]
+    mountpoint: String,
+    #[serde(default)]
+    created_at: String,
+    #[serde(default)]
+    labels: Option<HashMap<String, String>>,
+    #[serde(default)]
+    usage_data: Option<ApiVolumeUsage>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiVolumeUsage {
+    // -1 if not available
+    size: i64,
+    ref_count: i64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ApiVolumeList {
+    #[serde(default)]
+    volumes: Option<Vec<ApiVolume>>,
+}
+
+impl ApiVolume {
+    fn into_info(self) -> VolumeInfo {
+        VolumeInfo {
+            name: self.name,
+            driver: self.driver,
+            mountpoint: self.mountpoint,
+            created_at: self.created_at,
+            labels: self.labels.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiIdResponse {
+    #[serde(rename = "Id")]
+    id: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ApiStats {
+    cpu_stats: ApiCpuStats,
+    precpu_stats: ApiCpuStats,
+    memory_stats: ApiMemoryStats,
+    networks: HashMap<String, ApiNetworkStats>,
+    pids_stats: ApiPidsStats,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ApiCpuStats {
+    cpu_usage: ApiCpuUsage,
+    system_cpu_usage: u64,
+    online_cpus: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ApiCpuUsage {
+    total_usage: u64,
+    percpu_usage: Vec<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ApiMemoryStats {
+    usage: u64,
+    limit: u64,
+    stats: HashMap<String, u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ApiNetworkStats {
+    rx_bytes: u64,
+    tx_bytes: u64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ApiPidsStats {
+    current: u64,
+}
+
+impl ApiStats {
+    // Same arithmetic as `docker stats`
+    fn into_stats(self) -> ContainerStats {
+        let cpu_delta = self.cpu_stats.cpu_usage.total_usage.saturating_sub(self.precpu_stats.cpu_usage.total_usage);
+        let system_delta = self.cpu_stats.system_cpu_usage.saturating_sub(self.precpu_stats.system_cpu_usage);
+        let cpus = match self.cpu_stats.online_cpus {
+            0 => self.cpu_stats.cpu_usage.percpu_usage.len().max(1) as u64,
+            n => n,
+        };
+        let cpu_percent = if cpu_delta > 0 && system_delta > 0 {
+            cpu_delta as f64 / system_delta as f64 * cpus as f64 * 100.0
+        } else {
+            0.0
+        };
+        // Page cache doesn't count as used (cgroup v2 reports inactive_file, v1 cache)
+        let cache = self.memory_stats.stats.get("inactive_file")
+            .or_else(|| self.memory_stats.stats.get("cache"))
+            .copied()
+            .unwrap_or(0);
+        ContainerStats {
+            cpu_percent,
+            memory_bytes: self.memory_stats.usage.saturating_sub(cache),
+            memory_limit: self.memory_stats.limit,
+            net_rx_bytes: self.networks.values().map(|n| n.rx_bytes).sum(),
+            net_tx_bytes: self.networks.values().map(|n| n.tx_bytes).sum(),
+            pids: self.pids_stats.current,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiEvent {
+    #[serde(rename = "Type", default)]
+    kind: String,
+    #[serde(rename = "Action", default)]
+    action: String,
+    #[serde(rename = "Actor", default)]
+    actor: ApiEventActor,
+}
+
+#[derive(Deserialize, Default)]
+struct ApiEventActor {
+    #[serde(rename = "Attributes", default)]
+    attributes: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ApiBuildMessage {
+    stream: Option<String>,
+    status: Option<String>,
+    error: Option<String>,
+    aux: Option<serde_json::Value>,
+}
+
+impl DockerEngine {
+    pub fn new(socket_path: &str) -> Self {
+        DockerEngine { socket_path: socket_path.to_string() }
+    }
+
+    pub fn from_env() -> Self {
+        let socket = std::env::var("DOCKER_HOST")
+            .ok()
+            .and_then(|h| h.strip_prefix("unix://").map(|s| s.to_string()))
+            .unwrap_or_else(|| DEFAULT_DOCKER_SOCKET.to_string());
+        DockerEngine::new(&socket)
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        content_type: &str,
+        body: Bytes,
+    ) -> Result<hyper::Response<hyper::body::Incoming>, RuntimeError> {
+        let mut sender = self.connect().await?;
+
+        let request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(hyper::header::HOST, "docker")
+            .header(hyper::header::CONTENT_TYPE, content_type)
+            .body(Full::new(body))
+            .map_err(|e| RuntimeError::Io(e.to_string()))?;
+
+        let response = sender
+            .send_request(request)
+            .await
+            .map_err(|e| RuntimeError::Io(e.to_string()))?;
+
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        Err(api_error(response).await)
+    }
+
+    async fn connect(&self) -> Result<hyper::client::conn::http1::SendRequest<Full<Bytes>>, RuntimeError> {
+        let stream = UnixStream::connect(&self.socket_path)
+            .await
+            .map_err(|e| RuntimeError::Io(format!("{}: {}", self.socket_path, e)))?;
+        let (sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+            .await
+            .map_err(|e| RuntimeError::Io(e.to_string()))?;
+        tokio::spawn(async move {
+            // Upgrades let attach/exec take over the connection as a raw stream
+            let _ = conn.with_upgrades().await;
+        });
+        Ok(sender)
+    }
+
+    // POSTs `body` asking the daemon to hijack the connection, as `docker exec -it` does
+    async fn upgrade(&self, path: &str, body: &serde_json::Value) -> Result<TokioIo<hyper::upgrade::Upgraded>, RuntimeError> {
+        let mut sender = self.connect().await?;
+        let payload = serde_json::to_vec(body).map_err(|e| RuntimeError::Io(e.to_string()))?;
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(path)
+            .header(hyper::header::HOST, "docker")
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .header(hyper::header::CONNECTION, "Upgrade")
+            .header(hyper::header::UPGRADE, "tcp")
+            .body(Full::new(Bytes::from(payload)))
+            .map_err(|e| RuntimeError::Io(e.to_string()))?;
+        let response = sender
+            .send_request(request)
+            .await
+            .map_err(|e| RuntimeError::Io(e.to_string()))?;
+        if !response.status().is_success() && response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            return Err(api_error(response).await);
+        }
+        let upgraded = hyper::upgrade::on(response)
+            .await
+            .map_err(|e| RuntimeError::Io(e.to_string()))?;
+        Ok(TokioIo::new(upgraded))
+    }
+
+    async fn send_json(
+        &self,
+        method: Method,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<Vec<u8>, RuntimeError> {
+        let payload = serde_json::to_vec(body).map_err(|e| RuntimeError::Io(e.to_string()))?;
+        let response = self.send(method, path, "application/json", Bytes::from(payload)).await?;
+        read_body(response).await
+    }
+
+    async fn get(&self, path: &str) -> Result<Vec<u8>, RuntimeError> {
+        let response = self.send(Method::GET, path, "application/json", Bytes::new()).await?;
+        read_body(response).await
+    }
+}
+
+// Turns a failed response into a RuntimeError using the daemon's message
+async fn api_error(response: hyper::Response<hyper::body::Incoming>) -> RuntimeError {
+    let status = response.status();
+    let body = read_body(response).await.unwrap_or_default();
+    let message = serde_json::from_slice::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(|m| m.to_string()))
+        .unwrap_or_else(|| String::from_utf8_lossy(&body).trim().to_string());
+    match status {
+        StatusCode::NOT_FOUND => RuntimeError::NotFound(message),
+        StatusCode::CONFLICT => RuntimeError::Conflict(message),
+        _ => RuntimeError::Api { status: status.as_u16(), message },
+    }
+}
+
+async fn read_body(response: hyper::Response<hyper::body::Incoming>) -> Result<Vec<u8>, RuntimeError> {
+    response
+        .into_body()
+        .collect()
+        .await
+        .map(|b| b.to_bytes().to_vec())
+        .map_err(|e| RuntimeError::Io(e.to_string()))
+}
+
+fn query(pairs: &[(&str, &str)]) -> String {
+    serde_urlencoded::to_string(pairs).unwrap_or_default()
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, RuntimeError> {
+    serde_json::from_slice(body).map_err(|e| RuntimeError::Io(format!("unexpected docker response: {}", e)))
+}
+
+// Tars up a build context directory the way `docker build` sends it
+fn tar_build_context(dir: &str) -> Result<Vec<u8>, RuntimeError> {
+    let mut builder = tar::Builder::new(Vec::new());
+    builder.follow_symlinks(false);
+    builder
+        .append_dir_all(".", dir)
+        .map_err(|e| RuntimeError::Build(format!("failed to read build context {}: {}", dir, e)))?;
+    builder
+        .into_inner()
+        .map_err(|e| RuntimeError::Build(format!("failed to pack build context: {}", e)))
+}
+
+// Splits the multiplexed stdout/stderr stream returned by logs/attach for non-TTY containers
+fn demux_docker_stream(raw: &[u8]) -> String {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut rest = raw;
+    while rest.len() >= 8 {
+        let kind = rest[0];
+        if kind > 2 || rest[1..4] != [0, 0, 0] {
+            // Not multiplexed (TTY container) — return as-is
+            return String::from_utf8_lossy(raw).to_string();
+        }
+        let size = u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let end = (8 + size).min(rest.len());
+        out.extend_from_slice(&rest[8..end]);
+        rest = &rest[end..];
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+// Incremental demux_docker_stream that keeps stdout and stderr apart and
+// only emits complete lines
+#[derive(Default)]
+struct LogDemuxer {
+    pending: Vec<u8>,
+    // Unterminated line per stream (stdout, stderr)
+    partial: [Vec<u8>; 2],
+    // Set once the stream turns out not to be multiplexed (TTY container)
+    raw: bool,
+}
+
+impl LogDemuxer {
+    fn push(&mut self, chunk: &[u8]) -> Vec<(LogStream, String)> {
+        self.pending.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        loop {
+            if self.raw {
+                let data = std::mem::take(&mut self.pending);
+                self.split(LogStream::Stdout, &data, &mut lines);
+                break;
+            }
+            if self.pending.len() < 8 {
+                break;
+            }
+            let kind = self.pending[0];
+            if kind > 2 || self.pending[1..4] != [0, 0, 0] {
+                self.raw = true;
+                continue;
+            }
+            let size = u32::from_be_bytes([self.pending[4], self.pending[5], self.pending[6], self.pending[7]]) as usize;
+            if self.pending.len() < 8 + size {
+                break;
+            }
+            let frame: Vec<u8> = self.pending.drain(..8 + size).skip(8).collect();
+            let stream = if kind == 2 { LogStream::Stderr } else { LogStream::Stdout };
+            self.split(stream, &frame, &mut lines);
+        }
+        lines
+    }
+
+    fn split(&mut self, stream: LogStream, data: &[u8], lines: &mut Vec<(LogStream, String)>) {
+        let buf = &mut self.partial[stream as usize];
+        buf.extend_from_slice(data);
+        while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buf.drain(..=pos).collect();
+            let text = String::from_utf8_lossy(&line[..pos]);
+            lines.push((stream, text.trim_end_matches('\r').to_string()));
+        }
+    }
+
+    // Whatever is left once the log ends without a trailing newline
+    fn finish(&mut self) -> Vec<(LogStream, String)> {
+        [LogStream::Stdout, LogStream::Stderr]
+            .into_iter()
+            .map(|s| (s, std::mem::take(&mut self.partial[s as usize])))
+            .filter(|(_, rest)| !rest.is_empty())
+            .map(|(s, rest)| (s, String::from_utf8_lossy(&rest).to_string()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for DockerEngine {
+    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, RuntimeError> {
+        let path = format!("/containers/json?{}", query(&[("all", if all { "1" } else { "0" })]));
+        let containers: Vec<ApiContainer> = parse_json(&self.get(&path).await?)?;
+        Ok(containers
+            .into_iter()
+            .map(|c| ContainerInfo {
+                id: c.id,
+                name: c.names.first().map(|n| n.trim_start_matches('/').to_string()).unwrap_or_default(),
+                image: c.image,
+                state: c.state,
+                status: c.status,
+                labels: c.labels.unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn build_image(&self, spec: &BuildSpec, output: &LogSink) -> Result<String, RuntimeError> {
+        let context_dir = spec.context_dir.clone();
+        let context = tokio::task::spawn_blocking(move || tar_build_context(&context_dir))
+            .await
+            .map_err(|e| RuntimeError::Build(e.to_string()))??;
+
+        let mut params = vec![("t", spec.tag.as_str()), ("rm", "1"), ("forcerm", "1")];
+        if let Some(network) = &spec.network_mode {
+            params.push(("networkmode", network.as_str()));
+        }
+        let path = format!("/build?{}", query(&params));
+        let response = self
+            .send(Method::POST, &path, "application/x-tar", Bytes::from(context))
+            .await?;
+
+        // The build endpoint streams newline-delimited JSON progress messages
+        let mut body = response.into_body();
+        let mut pending: Vec<u8> = Vec::new();
+        let mut image_id = String::new();
+        let mut failure: Option<String> = None;
+        while let Some(frame) = body.frame().await {
+            let frame = frame.map_err(|e| RuntimeError::Io(e.to_string()))?;
+            let Some(chunk) = frame.data_ref() else { continue };
+            pending.extend_from_slice(chunk);
+            while let Some(pos) = pending.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=pos).collect();
+                let Ok(msg) = serde_json::from_slice::<ApiBuildMessage>(&line) else { continue };
+                if let Some(text) = msg.stream.or(msg.status) {
+                    for l in text.lines().filter(|l| !l.trim().is_empty()) {
+                        let _ = output.send(l.to_string());
+                    }
+                }
+                if let Some(err) = msg.error {
+                    let _ = output.send(err.clone());
+                    failure = Some(err);
+                }
+                if let Some(id) = msg.aux.as_ref().and_then(|a| a.get("ID")).and_then(|v| v.as_str()) {
+                    image_id = id.to_string();
+                }
+            }
+        }
+
+        match failure {
+            Some(err) => Err(RuntimeError::Build(err)),
+            None => Ok(image_id),
+        }
+    }
+
+    async fn tag_image(&self, image: &str, repo: &str, tag: &str) -> Result<(), RuntimeError> {
+        let path = format!("/images/{}/tag?{}", image, query(&[("repo", repo), ("tag", tag)]));
+        self.send(Method::POST, &path, "application/json", Bytes::new()).await?;
+        Ok(())
+    }
+
+    async fn remove_image(&self, image: &str) -> Result<(), RuntimeError> {
+        self.send(Method::DELETE, &format!("/images/{}", image), "application/json", Bytes::new()).await?;
+        Ok(())
+    }
+
+    async fn run_container(&self, spec: &RunSpec) -> Result<String, RuntimeError> {
+        let mut host_config = serde_json::json!({ "Binds": spec.binds });
+        if let Some(network) = &spec.network {
+            host_config["NetworkMode"] = serde_json::json!(network);
+        }
+        if let Some(memory) = spec.memory_bytes {
+            host_config["Memory"] = serde_json::json!(memory);
+        }
+        if let Some(nano_cpus) = spec.nano_cpus {
+            host_config["NanoCpus"] = serde_json::json!(nano_cpus);
+        }
+        if let Some(pids) = spec.pids_limit {
+            host_config["PidsLimit"] = serde_json::json!(pids);
+        }
+        if let Some(restart) = &spec.restart {
+            host_config["RestartPolicy"] = serde_json::json!({
+                "Name": restart.name,
+                "MaximumRetryCount": restart.max_retries,
+            });
+        }
+        let mut body = serde_json::json!({
+            "Image": spec.image,
+            "Env": spec.env,
+            "Labels": spec.labels,
+            "HostConfig": host_config,
+        });
+        if let Some(network) = &spec.network
+            && !spec.network_aliases.is_empty()
+        {
+            body["NetworkingConfig"] = serde_json::json!({
+                "EndpointsConfig": { network: { "Aliases": spec.network_aliases } },
+            });
+        }
+        if let Some(hc) = &spec.healthcheck {
+            const NANOS: u64 = 1_000_000_000;
+            body["Healthcheck"] = serde_json::json!({
+                "Test": hc.test,
+                "Interval": hc.interval_secs * NANOS,
+                "Timeout": hc.timeout_secs * NANOS,
+                "Retries": hc.retries,
+            });
+        }
+        let path = format!("/containers/create?{}", query(&[("name", &spec.name)]));
+        let created: ApiIdResponse = parse_json(&self.send_json(Method::POST, &path, &body).await?)?;
+
+        let start_path = format!("/containers/{}/start", created.id);
+        self.send(Method::POST, &start_path, "application/json", Bytes::new()).await?;
+        Ok(created.id)
+    }
+
+    async fn remove_container(&self, name: &str) -> Result<(), RuntimeError> {
+        let path = format!("/containers/{}?{}", name, query(&[("force", "1")]));
+        self.send(Method::DELETE, &path, "application/json", Bytes::new()).await?;
+        Ok(())
+    }
+
+    async fn container_logs(&self, name: &str, tail: usize) -> Result<String, RuntimeError> {
+        let tail = tail.to_string();
+        let path = format!(
+            "/containers/{}/logs?{}",
+            name,
+            query(&[("stdout", "1"), ("stderr", "1"), ("tail", &tail)])
+        );
+        Ok(demux_docker_stream(&self.get(&path).await?))
+    }
+
+    async fn stream_logs(&self, name: &str, options: &LogOptions, output: &LogLineSink) -> Result<(), RuntimeError> {
+        let flag = |on: bool| if on { "1" } else { "0" };
+        let tail = options.tail.map(|t| t.to_string()).unwrap_or_else(|| "all".to_string());
+        let since = options.since.map(|t| t.to_string());
+        let until = options.until.map(|t| t.to_string());
+        let mut params = vec![
+            ("stdout", flag(options.stdout)),
+            ("stderr", flag(options.stderr)),
+            ("timestamps", flag(options.timestamps)),
+            ("follow", flag(options.follow)),
+            ("tail", tail.as_str()),
+        ];
+        if let Some(since) = &since {
+            params.push(("since", since));
+        }
+        if let Some(until) = &until {
+            params.push(("until", until));
+        }
+        let path = format!("/containers/{}/logs?{}", name, query(&params));
+        let response = self.send(Method::GET, &path, "application/json", Bytes::new()).await?;
+
+        let mut body = response.into_body();
+        let mut demux = LogDemuxer::default();
+        loop {
+            let frame = tokio::select! {
+                frame = body.frame() => frame,
+                // Nobody is listening any more
+                _ = output.closed() => return Ok(()),
+            };
+            let Some(frame) = frame else { break };
+            let frame = frame.map_err(|e| RuntimeError::Io(e.to_string()))?;
+            let Some(chunk) = frame.data_ref() else { continue };
+            for line in demux.push(chunk) {
+                if output.send(line).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        for line in demux.finish() {
+            let _ = output.send(line);
+        }
+        Ok(())
+    }
+
+    async fn inspect_container(&self, name: &str) -> Result<ContainerDetails, RuntimeError> {
+        let c: ApiContainerInspect = parse_json(&self.get(&format!("/containers/{}/json", name)).await?)?;
+        let ip_addresses = c
+            .network_settings
+            .and_then(|n| n.networks)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, e)| !e.ip_address.is_empty())
+            .map(|(net, e)| (net, e.ip_address))
+            .collect();
+        Ok(ContainerDetails {
+            id: c.id,
+            name: c.name.trim_start_matches('/').to_string(),
+            image: c.image,
+            state: c.state.status,
+            running: c.state.running,
+            exit_code: c.state.exit_code,
+            restart_count: c.restart_count,
+            started_at: c.state.started_at,
+            finished_at: c.state.finished_at,
+            health: c.state.health.map(|h| h.status),
+            ip_addresses,
+            labels: c.config.and_then(|c| c.labels).unwrap_or_default(),
+        })
+    }
+
+    async fn container_stats(&self, name: &str) -> Result<ContainerStats, RuntimeError> {
+        // stream=false waits for a second sample so precpu_stats is filled in
+        let path = format!("/containers/{}/stats?{}", name, query(&[("stream", "false")]));
+        let stats: ApiStats = parse_json(&self.get(&path).await?)?;
+        Ok(stats.into_stats())
+    }
+
+    async fn watch_events(&self, output: &UnboundedSender<ContainerEvent>) -> Result<(), RuntimeError> {
+        let filters = r#"{"type":["container"]}"#;
+        let path = format!("/events?{}", query(&[("filters", filters)]));
+        let response = self.send(Method::GET, &path, "application/json", Bytes::new()).await?;
+
+        // One JSON object per line, for as long as the connection stays open
+        let mut body = response.into_body();
+        let mut pending: Vec<u8> = Vec::new();
+        loop {
+            let frame = tokio::select! {
+                frame = body.frame() => frame,
+                _ = output.closed() => return Ok(()),
+            };
+            let Some(frame) = frame else { break };
+            let frame = frame.map_err(|e| RuntimeError::Io(e.to_string()))?;
+            let Some(chunk) = frame.data_ref() else { continue };
+            pending.extend_from_slice(chunk);
+            while let Some(pos) = pending.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=pos).collect();
+                let Ok(event) = serde_json::from_slice::<ApiEvent>(&line) else { continue };
+                if event.kind != "container" {
+                    continue;
+                }
+                let mut attributes = event.actor.attributes;
+                let Some(name) = attributes.remove("name") else { continue };
+                let old_name = attributes.remove("oldName").map(|n| n.trim_start_matches('/').to_string());
+                if output.send(ContainerEvent { action: event.action, name, old_name }).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        Err(RuntimeError::Io("event stream closed".to_string()))
+    }
+
+    async fn rename_container(&self, name: &str, new_name: &str) -> Result<(), RuntimeError> {
+        let path = format!("/containers/{}/rename?{}", name, query(&[("name", new_name)]));
+        self.send(Method::POST, &path, "application/json", Bytes::new()).await?;
+        Ok(())
+    }
+
+    async fn http_probe(&self, name: &str, network: Option<&str>, port: u16, path: &str) -> Result<u16, RuntimeError> {
+        let details = self.inspect_container(name).await?;
+        let ip = network
+            .and_then(|n| details.ip_addresses.get(n))
+            .or_else(|| details.ip_addresses.values().next())
+            .ok_or_else(|| RuntimeError::Io(format!("{} has no IP address", name)))?;
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(3))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| RuntimeError::Io(e.to_string()))?;
+        let response = client
+            .get(format!("http://{}:{}{}", ip, port, path))
+            .send()
+            .await
+            .map_err(|e| RuntimeError::Io(e.to_string()))?;
+        Ok(response.status().as_u16())
+    }
+
+    async fn exec(&self, name: &str, cmd: &[String], detach: bool) -> Result<(), RuntimeError> {
+        let create = serde_json::json!({
+            "Cmd": cmd,
+            "AttachStdout": !detach,
+            "AttachStderr": !detach,
+        });
+        let path = format!("/containers/{}/exec", name);
+        let exec: ApiIdResponse = parse_json(&self.send_json(Method::POST, &path, &create).await?)?;
+
+        let start = serde_json::json!({ "Detach": detach, "Tty": false });
+        // Without detach the daemon streams the output until the process exits
+        self.send_json(Method::POST, &format!("/exec/{}/start", exec.id), &start).await?;
+        Ok(())
+    }
+
+    async fn exec_interactive(&self, name: &str, cmd: &[String], cols: u16, rows: u16) -> Result<ExecSession, RuntimeError> {
+        let create = serde_json::json!({
+            "Cmd": cmd,
+            "AttachStdin": true,
+            "AttachStdout": true,
+            "AttachStderr": true,
+            "Tty": true,
+            "Env": ["TERM=xterm-256color"],
+        });
+        let path = format!("/containers/{}/exec", name);
+        let exec: ApiIdResponse = parse_json(&self.send_json(Method::POST, &path, &create).await?)?;
+
+        let start = serde_json::json!({ "Detach": false, "Tty": true });
+        let io = self.upgrade(&format!("/exec/{}/start", exec.id), &start).await?;
+        // The TTY can only be sized once the process is running
+        let _ = self.resize_exec(&exec.id, cols, rows).await;
+        Ok(ExecSession { id: exec.id, io: Box::new(io) })
+    }
+
+    async fn resize_exec(&self, exec_id: &str, cols: u16, rows: u16) -> Result<(), RuntimeError> {
+        let (w, h) = (cols.to_string(), rows.to_string());
+        let path = format!("/exec/{}/resize?{}", exec_id, query(&[("h", h.as_str()), ("w", w.as_str())]));
+        self.send(Method::POST, &path, "application/json", Bytes::new()).await?;
+        Ok(())
+    }
+
+    async fn create_volume(&self, name: &str, labels: &BTreeMap<String, String>) -> Result<VolumeInfo, RuntimeError> {
+        let body = serde_json::json!({ "Name": name, "Driver": "local", "Labels": labels });
+        let volume: ApiVolume = parse_json(&self.send_json(Method::POST, "/volumes/create", &body).await?)?;
+        Ok(volume.into_info())
+    }
+
+    async fn list_volumes(&self, label: &str) -> Result<Vec<VolumeInfo>, RuntimeError> {
+        let filters = serde_json::json!({ "label": [label] }).to_string();
+        let path = format!("/volumes?{}", query(&[("filters", &filters)]));
+        let list: ApiVolumeList = parse_json(&self.get(&path).await?)?;
+        Ok(list.volumes.unwrap_or_default().into_iter().map(ApiVolume::into_info).collect())
+    }
+
+    async fn inspect_volume(&self, name: &str) -> Result<VolumeInfo, RuntimeError> {
+        let volume: ApiVolume = parse_json(&self.get(&format!("/volumes/{}", name)).await?)?;
+        Ok(volume.into_info())
+    }
+
+    async fn volume_usage(&self) -> Result<HashMap<String, VolumeUsage>, RuntimeError> {
+        let path = format!("/system/df?{}", query(&[("type", "volume")]));
+        let df: ApiVolumeList = parse_json(&self.get(&path).await?)?;
+        Ok(df
+            .volumes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|v| {
+                let usage = v.usage_data.map(|u| VolumeUsage {
+                    size_bytes: u64::try_from(u.size).ok(),
+                    ref_count: u.ref_count,
+                });
+                (v.name, usage.unwrap_or_default())
+            })
+            .collect())
+    }
+
+    async fn remove_volume(&self, name: &str) -> Result<(), RuntimeError> {
+        self.send(Method::DELETE, &format!("/volumes/{}", name), "application/json", Bytes::new()).await?;
+        Ok(())
+    }
+}
+
+// ── In-memory fake runtime (no Docker daemon required) ──
+
+#[derive(Default)]
+pub struct FakeRuntime {
+    containers: Mutex<HashMap<String, ContainerInfo>>,
+    // Image reference -> image ID
+    images: Mutex<HashMap<String, String>>,
+    // Container ID -> image ID it was created from
+    container_images: Mutex<HashMap<String, String>>,
+    logs: Mutex<HashMap<String, Vec<String>>>,
+    // Container ID -> start time
+    started_at: Mutex<HashMap<String, String>>,
+    event_sinks: Mutex<Vec<UnboundedSender<ContainerEvent>>>,
+    volumes: Mutex<HashMap<String, VolumeInfo>>,
+    // Container name -> named volumes it mounts
+    container_volumes: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl FakeRuntime {
+    fn record_log(&self, name: &str, line: String) {
+        self.logs.lock().unwrap().entry(name.to_string()).or_default().push(line);
+    }
+
+    // Existing volume of that name, or a new one
+    fn volume_entry(&self, name: &str, labels: &BTreeMap<String, String>) -> VolumeInfo {
+        let mut volumes = self.volumes.lock().unwrap();
+        let volume = volumes.entry(name.to_string()).or_insert_with(|| VolumeInfo {
+            name: name.to_string(),
+            driver: "local".to_string(),
+            mountpoint: format!("/var/lib/docker/volumes/{}/_data", name),
+            created_at: crate::now_iso8601(),
+            labels: labels.clone().into_iter().collect(),
+        });
+        volume.clone()
+    }
+
+    fn emit(&self, action: &str, name: &str, old_name: Option<&str>) {
+        let event = ContainerEvent {
+            action: action.to_string(),
+            name: name.to_string(),
+            old_name: old_name.map(|n| n.to_string()),
+        };
+        self.event_sinks.lock().unwrap().retain(|sink| sink.send(event.clone()).is_ok());
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for FakeRuntime {
+    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, RuntimeError> {
+        let containers = self.containers.lock().unwrap();
+        let mut list: Vec<ContainerInfo> = containers
+            .values()
+            .filter(|c| all || c.is_running())
+            .cloned()
+            .collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(list)
+    }
+
+    async fn build_image(&self, spec: &BuildSpec, output: &LogSink) -> Result<String, RuntimeError> {
+        let dockerfile = std::path::Path::new(&spec.context_dir).join("Dockerfile");
+        if !dockerfile.exists() {
+            return Err(RuntimeError::Build(format!("no Dockerfile in {}", spec.context_dir)));
+        }
+        let _ = output.send(format!("Step 1/1 : building {} (fake)", spec.tag));
+        let id = format!("sha256:{:016x}", rand::random::<u64>());
+        let _ = output.send(format!("Successfully built {}", id));
+        self.images.lock().unwrap().insert(spec.tag.clone(), id.clone());
+        Ok(id)
+    }
+
+    async fn tag_image(&self, image: &str, repo: &str, tag: &str) -> Result<(), RuntimeError> {
+        let mut images = self.images.lock().unwrap();
+        let id = images
+            .get(image)
+            .cloned()
+            .or_else(|| images.values().find(|id| *id == image).cloned())
+            .ok_or_else(|| RuntimeError::NotFound(format!("No such image: {}", image)))?;
+        images.insert(format!("{}:{}", repo, tag), id);
+        Ok(())
+    }
+
+    async fn remove_image(&self, image: &str) -> Result<(), RuntimeError> {
+        match self.images.lock().unwrap().remove(image) {
+            Some(_) => Ok(()),
+            None => Err(RuntimeError::NotFound(format!("No such image: {}", image))),
+        }
+    }
+
+    async fn run_container(&self, spec: &RunSpec) -> Result<String, RuntimeError> {
+        let Some(image_id) = self.images.lock().unwrap().get(&spec.image).cloned() else {
+            return Err(RuntimeError::NotFound(format!("No such image: {}", spec.image)));
+        };
+        let mut containers = self.containers.lock().unwrap();
+        if containers.contains_key(&spec.name) {
+            return Err(RuntimeError::Conflict(format!(
+                "The container name \"/{}\" is already in use",
+                spec.name
+            )));
+        }
+        let id = format!("{:016x}", rand::random::<u64>());
+        containers.insert(spec.name.clone(), ContainerInfo {
+            id: id.clone(),
+            name: spec.name.clone(),
+            image: spec.image.clone(),
+            state: "running".to_string(),
+            status: "Up Less than a second".to_string(),
+            labels: spec.labels.clone().into_iter().collect(),
+        });
+        drop(containers);
+        // Sources that aren't host paths are named volumes, created on first use like Docker does
+        let volumes: Vec<String> = spec.binds
+            .iter()
+            .filter_map(|b| b.split(':').next())
+            .filter(|source| !source.starts_with('/'))
+            .map(|source| source.to_string())
+            .collect();
+        for volume in &volumes {
+            self.volume_entry(volume, &BTreeMap::new());
+        }
+        self.container_volumes.lock().unwrap().insert(spec.name.clone(), volumes);
+        self.container_images.lock().unwrap().insert(id.clone(), image_id);
+        self.started_at.lock().unwrap().insert(id.clone(), crate::now_iso8601());
+        self.record_log(&spec.name, format!("{} started (fake)", spec.name));
+        self.emit("start", &spec.name, None);
+        Ok(id)
+    }
+
+    async fn remove_container(&self, name: &str) -> Result<(), RuntimeError> {
+        self.logs.lock().unwrap().remove(name);
+        self.container_volumes.lock().unwrap().remove(name);
+        let removed = self.containers.lock().unwrap().remove(name);
+        match removed {
+            Some(_) => {
+                self.emit("destroy", name, None);
+                Ok(())
+            }
+            None => Err(RuntimeError::NotFound(format!("No such container: {}", name))),
+        }
+    }
+
+    async fn container_logs(&self, name: &str, tail: usize) -> Result<String, RuntimeError> {
+        if !self.containers.lock().unwrap().contains_key(name) {
+            return Err(RuntimeError::NotFound(format!("No such container: {}", name)));
+        }
+        let logs = self.logs.lock().unwrap();
+        let lines = logs.get(name).map(|l| l.as_slice()).unwrap_or_default();
+        let start = lines.len().saturating_sub(tail);
+        Ok(lines[start..].iter().map(|l| format!("{}\n", l)).collect())
+    }
+
+    // Fake logs are all stdout, have no timestamps and never grow, so
+    // following just waits for the listener to go away
+    async fn stream_logs(&self, name: &str, options: &LogOptions, output: &LogLineSink) -> Result<(), RuntimeError> {
+        let logs = self.container_logs(name, options.tail.unwrap_or(usize::MAX)).await?;
+        if options.stdout {
+            for line in logs.lines() {
+                let _ = output.send((LogStream::Stdout, line.to_string()));
+            }
+        }
+        if options.follow {
+            output.closed().await;
+        }
+        Ok(())
+    }
+
+    async fn inspect_container(&self, name: &str) -> Result<ContainerDetails, RuntimeError> {
+        let containers = self.containers.lock().unwrap();
+        let c = containers
+            .get(name)
+            .ok_or_else(|| RuntimeError::NotFound(format!("No such container: {}", name)))?;
+        Ok(ContainerDetails {
+            id: c.id.clone(),
+            name: c.name.clone(),
+            image: self.container_images.lock().unwrap().get(&c.id).cloned().unwrap_or_default(),
+            state: c.state.clone(),
+            running: c.is_running(),
+            started_at: self.started_at.lock().unwrap().get(&c.id).cloned().unwrap_or_default(),
+            labels: c.labels.clone(),
+            ..Default::default()
+        })
+    }
+
+    // Made-up but plausible numbers so the stats views have something to show
+    async fn container_stats(&self, name: &str) -> Result<ContainerStats, RuntimeError> {
+        if !self.inspect_container(name).await?.running {
+            return Err(RuntimeError::Conflict(format!("Container {} is not running", name)));
+        }
+        let lines = self.logs.lock().unwrap().get(name).map(|l| l.len()).unwrap_or(0) as u64;
+        Ok(ContainerStats {
+            cpu_percent: rand::random::<f64>() * 5.0,
+            memory_bytes: 64 * 1024 * 1024 + rand::random::<u64>() % (16 * 1024 * 1024),
+            memory_limit: 2 * 1024 * 1024 * 1024,
+            net_rx_bytes: lines * 1024,
+            net_tx_bytes: lines * 512,
+            pids: 4,
+        })
+    }
+
+    async fn watch_events(&self, output: &UnboundedSender<ContainerEvent>) -> Result<(), RuntimeError> {
+        self.event_sinks.lock().unwrap().push(output.clone());
+        output.closed().await;
+        Ok(())
+    }
+
+    async fn rename_container(&self, name: &str, new_name: &str) -> Result<(), RuntimeError> {
+        let mut containers = self.containers.lock().unwrap();
+        if containers.contains_key(new_name) {
+            return Err(RuntimeError::Conflict(format!("The container name \"/{}\" is already in use", new_name)));
+        }
+        let mut container = containers
+            .remove(name)
+            .ok_or_else(|| RuntimeError::NotFound(format!("No such container: {}", name)))?;
+        container.name = new_name.to_string();
+        containers.insert(new_name.to_string(), container);
+        drop(containers);
+        let mut logs = self.logs.lock().unwrap();
+        if let Some(lines) = logs.remove(name) {
+            logs.insert(new_name.to_string(), lines);
+        }
+        drop(logs);
+        self.emit("rename", new_name, Some(name));
+        Ok(())
+    }
+
+    // Fake containers answer every probe as soon as they are running
+    async fn http_probe(&self, name: &str, _network: Option<&str>, _port: u16, _path: &str) -> Result<u16, RuntimeError> {
+        match self.inspect_container(name).await? {
+            details if details.running => Ok(200),
+            _ => Err(RuntimeError::Io(format!("{} is not running", name))),
+        }
+    }
+
+    async fn exec(&self, name: &str, cmd: &[String], _detach: bool) -> Result<(), RuntimeError> {
+        if !self.containers.lock().unwrap().contains_key(name) {
+            return Err(RuntimeError::NotFound(format!("No such container: {}", name)));
+        }
+        self.record_log(name, format!("exec: {}", cmd.join(" ")));
+        Ok(())
+    }
+
+    // Fake shells echo their input back like a TTY in cooked mode
+    async fn exec_interactive(&self, name: &str, cmd: &[String], _cols: u16, _rows: u16) -> Result<ExecSession, RuntimeError> {
+        if !self.containers.lock().unwrap().contains_key(name) {
+            return Err(RuntimeError::NotFound(format!("No such container: {}", name)));
+        }
+        self.record_log(name, format!("exec: {}", cmd.join(" ")));
+        let (client, mut shell) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            while let Ok(n) = shell.read(&mut buf).await {
+                // Ctrl-D ends the session
+                if n == 0 || buf[..n].contains(&4) {
+                    break;
+                }
+                let echoed: Vec<u8> = buf[..n]
+                    .iter()
+                    .flat_map(|b| if *b == b'\r' { b"\r\n".to_vec() } else { vec![*b] })
+                    .collect();
+                if shell.write_all(&echoed).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(ExecSession { id: format!("{:016x}", rand::random::<u64>()), io: Box::new(client) })
+    }
+
+    async fn resize_exec(&self, _exec_id: &str, _cols: u16, _rows: u16) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+    async fn create_volume(&self, name: &str, labels: &BTreeMap<String, String>) -> Result<VolumeInfo, RuntimeError> {
+        Ok(self.volume_entry(name, labels))
+    }
+
+    async fn list_volumes(&self, label: &str) -> Result<Vec<VolumeInfo>, RuntimeError> {
+        let (key, value) = label.split_once('=').unwrap_or((label, ""));
+        let mut list: Vec<VolumeInfo> = self.volumes.lock().unwrap()
+            .values()
+            .filter(|v| v.labels.get(key).is_some_and(|l| value.is_empty() || l == value))
+            .cloned()
+            .collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(list)
+    }
+
+    async fn inspect_volume(&self, name: &str) -> Result<VolumeInfo, RuntimeError> {
+        self.volumes.lock().unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| RuntimeError::NotFound(format!("get {}: no such volume", name)))
+    }
+
+    async fn volume_usage(&self) -> Result<HashMap<String, VolumeUsage>, RuntimeError> {
+        let in_use = self.container_volumes.lock().unwrap();
+        Ok(self.volumes.lock().unwrap()
+            .keys()
+            .map(|name| {
+                let ref_count = in_use.values().filter(|v| v.contains(name)).count() as i64;
+                (name.clone(), VolumeUsage { size_bytes: Some(0), ref_count })
+            })
+            .collect())
+    }
+
+    async fn remove_volume(&self, name: &str) -> Result<(), RuntimeError> {
+        if self.container_volumes.lock().unwrap().values().any(|v| v.iter().any(|n| n == name)) {
+            return Err(RuntimeError::Conflict(format!("remove {}: volume is in use", name)));
+        }
+        match self.volumes.lock().unwrap().remove(name) {
+            Some(_) => Ok(()),
+            None => Err(RuntimeError::NotFound(format!("get {}: no such volume", name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_with_image(image: &str) -> FakeRuntime {
+        let runtime = FakeRuntime::default();
+        runtime.images.lock().unwrap().insert(image.to_string(), "sha256:feedface".to_string());
+        runtime
+    }
+
+    // One Docker log frame: stream type, three zero bytes, big-endian length, payload
+    fn frame(kind: u8, payload: &str) -> Vec<u8> {
+        let mut out = vec![kind, 0, 0, 0];
+        out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        out.extend_from_slice(payload.as_bytes());
+        out
+    }
+
+    #[tokio::test]
+    async fn fake_container_lifecycle() {
+        let runtime = fake_with_image("demo:latest");
+        let mut spec = RunSpec::new("demo", "demo:latest");
+        spec.labels.insert("mcphub.app".to_string(), "demo".to_string());
+        let id = runtime.run_container(&spec).await.unwrap();
+
+        let details = runtime.inspect_container("demo").await.unwrap();
+        assert_eq!(details.id, id);
+        assert!(details.running);
+        assert_eq!(details.image, "sha256:feedface");
+        assert_eq!(details.labels.get("mcphub.app").map(String::as_str), Some("demo"));
+        let running: Vec<String> = runtime.list_containers(false).await.unwrap().into_iter().map(|c| c.name).collect();
+        assert_eq!(running, vec!["demo".to_string()]);
+        assert_eq!(runtime.container_logs("demo", 10).await.unwrap(), "demo started (fake)\n");
+
+        // Names are unique, like Docker's
+        assert!(matches!(runtime.run_container(&spec).await, Err(RuntimeError::Conflict(_))));
+
+        runtime.remove_container("demo").await.unwrap();
+        assert!(runtime.list_containers(true).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fake_reports_missing_containers_and_images() {
+        let runtime = FakeRuntime::default();
+        let not_found = |r: Result<_, RuntimeError>| matches!(r, Err(RuntimeError::NotFound(_)));
+        assert!(not_found(runtime.inspect_container("ghost").await.map(|_| ())));
+        assert!(not_found(runtime.remove_container("ghost").await));
+        assert!(not_found(runtime.container_logs("ghost", 10).await.map(|_| ())));
+        assert!(not_found(runtime.rename_container("ghost", "other").await));
+        assert!(not_found(runtime.run_container(&RunSpec::new("demo", "missing:latest")).await.map(|_| ())));
+        assert!(not_found(runtime.remove_image("missing:latest").await));
+    }
+
+    #[test]
+    fn demux_joins_frames_and_passes_tty_output_through() {
+        let mut raw = frame(1, "hello ");
+        raw.extend(frame(2, "world\n"));
+        assert_eq!(demux_docker_stream(&raw), "hello world\n");
+        // TTY containers aren't multiplexed
+        assert_eq!(demux_docker_stream(b"plain output here\n"), "plain output here\n");
+        // A truncated last frame keeps what arrived
+        let mut cut = frame(1, "complete\n");
+        cut.extend(&frame(1, "partial line")[..12]);
+        assert_eq!(demux_docker_stream(&cut), "complete\npart");
+    }
+
+    #[test]
+    fn log_demuxer_splits_streams_across_chunks() {
+        let mut demuxer = LogDemuxer::default();
+        let mut raw = frame(1, "out 1\nout ");
+        raw.extend(frame(2, "err 1\r\n"));
+        raw.extend(frame(1, "2\n"));
+        raw.extend(frame(2, "no newline"));
+
+        // Fed in small pieces, frames split anywhere
+        let mut lines = Vec::new();
+        for chunk in raw.chunks(5) {
+            lines.extend(demuxer.push(chunk));
+        }
+        lines.extend(demuxer.finish());
+        assert_eq!(lines, vec![
+            (LogStream::Stdout, "out 1".to_string()),
+            (LogStream::Stderr, "err 1".to_string()),
+            (LogStream::Stdout, "out 2".to_string()),
+            (LogStream::Stderr, "no newline".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn log_demuxer_treats_unframed_output_as_stdout() {
+        let mut demuxer = LogDemuxer::default();
+        let mut lines = demuxer.push(b"tty line one\ntty ");
+        lines.extend(demuxer.push(b"line two\n"));
+        assert_eq!(lines, vec![
+            (LogStream::Stdout, "tty line one".to_string()),
+            (LogStream::Stdout, "tty line two".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,405 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use crate::manifest::valid_env_name;
+use crate::{now_iso8601, AppState};
+
+// ── Per-app env vars and secrets ──
+//
+// Values are encrypted with AES-256-GCM before they hit /apps/app_env.json.
+// The key is derived (HKDF-SHA256) from MCPHUB_SECRETS_KEY, falling back to
+// MANAGER_PASSWORD, so changing either makes existing values undecryptable.
+// Secret values are write-only over the API; plain env vars can be read back.
+
+const APP_ENV_PATH: &str = "/apps/app_env.json";
+const KEY_SALT: &[u8] = b"mcphub-app-env-v1";
+const NONCE_LEN: usize = 12;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredVar {
+    secret: bool,
+    // base64(nonce || ciphertext)
+    value: String,
+    updated_at: String,
+}
+
+#[derive(Serialize)]
+pub struct EnvVarView {
+    name: String,
+    secret: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    updated_at: String,
+}
+
+#[derive(Clone)]
+pub struct SecretStore {
+    cipher: Arc<Aes256Gcm>,
+    entries: Arc<RwLock<HashMap<String, BTreeMap<String, StoredVar>>>>,
+    path: Arc<str>,
+}
+
+// A missing file is an empty store; one that can't be read or parsed is an
+// error, since saving over it would lose every app's values
+fn load_entries(path: &str) -> Result<HashMap<String, BTreeMap<String, StoredVar>>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(src) => serde_json::from_str(&src).map_err(|e| format!("{} is corrupt: {}", path, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path, e)),
+    }
+}
+
+// Written to a temporary file first so a crash never leaves a half-written store
+fn save_entries(path: &str, entries: &HashMap<String, BTreeMap<String, StoredVar>>) -> Result<(), std::io::Error> {
+    let json = serde_json::to_string_pretty(entries).map_err(std::io::Error::other)?;
+    let tmp = format!("{}.tmp", path);
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)
+}
+
+// Binds each ciphertext to its app and variable so values can't be swapped in the file
+fn aad(app_name: &str, name: &str) -> Vec<u8> {
+    format!("{}/{}", app_name, name).into_bytes()
+}
+
+impl SecretStore {
+    pub fn open(path: &str, key_material: &str) -> Result<Self, String> {
+        let hk = hkdf::Hkdf::<sha2::Sha256>::new(Some(KEY_SALT), key_material.as_bytes());
+        let mut key = [0u8; 32];
+        hk.expand(b"aes-256-gcm", &mut key).expect("32 bytes is a valid HKDF output length");
+        Ok(SecretStore {
+            cipher: Arc::new(Aes256Gcm::new(&key.into())),
+            entries: Arc::new(RwLock::new(load_entries(path)?)),
+            path: path.into(),
+        })
+    }
+
+    pub fn from_env(manager_password: &str) -> Result<Self, String> {
+        let key_material = std::env::var("MCPHUB_SECRETS_KEY")
+            .ok()
+            .filter(|k| !k.is_empty())
+            .unwrap_or_else(|| manager_password.to_string());
+        let store = Self::open(APP_ENV_PATH, &key_material)?;
+        let count: usize = store.entries.read().unwrap().values().map(|v| v.len()).sum();
+        println!("Loaded {} app env entries", count);
+        Ok(store)
+    }
+
+    fn encrypt(&self, app_name: &str, name: &str, plaintext: &str) -> Result<String, String> {
+        let nonce_bytes: [u8; NONCE_LEN] = rand::random();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), Payload { msg: plaintext.as_bytes(), aad: &aad(app_name, name) })
+            .map_err(|_| "encryption failed".to_string())?;
+        let mut blob = nonce_bytes.to_vec();
+        blob.extend(ciphertext);
+        Ok(BASE64.encode(blob))
+    }
+
+    fn decrypt(&self, app_name: &str, name: &str, value: &str) -> Result<String, String> {
+        let undecryptable = || format!("cannot decrypt env var '{}' (secrets key changed?)", name);
+        let blob = BASE64.decode(value).map_err(|_| undecryptable())?;
+        if blob.len() < NONCE_LEN {
+            return Err(undecryptable());
+        }
+        let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &aad(app_name, name) })
+            .map_err(|_| undecryptable())?;
+        String::from_utf8(plaintext).map_err(|_| undecryptable())
+    }
+
+    fn view(&self, app_name: &str, name: &str, var: &StoredVar) -> EnvVarView {
+        EnvVarView {
+            name: name.to_string(),
+            secret: var.secret,
+            value: if var.secret { None } else { self.decrypt(app_name, name, &var.value).ok() },
+            updated_at: var.updated_at.clone(),
+        }
+    }
+
+    pub fn list(&self, app_name: &str) -> Vec<EnvVarView> {
+        let entries = self.entries.read().unwrap();
+        entries
+            .get(app_name)
+            .map(|vars| vars.iter().map(|(name, var)| self.view(app_name, name, var)).collect())
+            .unwrap_or_default()
+    }
+
+    // Names of the app's variables, with secret values masked
+    pub fn masked(&self, app_name: &str) -> BTreeMap<String, String> {
+        self.list(app_name)
+            .into_iter()
+            .map(|v| (v.name, v.value.unwrap_or_else(|| "********".to_string())))
+            .collect()
+    }
+
+    pub fn set(&self, app_name: &str, name: &str, value: &str, secret: bool) -> Result<EnvVarView, String> {
+        let var = StoredVar {
+            secret,
+            value: self.encrypt(app_name, name, value)?,
+            updated_at: now_iso8601(),
+        };
+        let view = self.view(app_name, name, &var);
+        let mut entries = self.entries.write().unwrap();
+        entries.entry(app_name.to_string()).or_default().insert(name.to_string(), var);
+        save_entries(&self.path, &entries).map_err(|e| format!("Failed to save: {}", e))?;
+        Ok(view)
+    }
+
+    pub fn remove(&self, app_name: &str, name: &str) -> Result<bool, String> {
+        let mut entries = self.entries.write().unwrap();
+        let Some(vars) = entries.get_mut(app_name) else { return Ok(false) };
+        if vars.remove(name).is_none() {
+            return Ok(false);
+        }
+        if vars.is_empty() {
+            entries.remove(app_name);
+        }
+        save_entries(&self.path, &entries).map_err(|e| format!("Failed to save: {}", e))?;
+        Ok(true)
+    }
+
+    pub fn remove_app(&self, app_name: &str) {
+        let mut entries = self.entries.write().unwrap();
+        if entries.remove(app_name).is_some() {
+            let _ = save_entries(&self.path, &entries);
+        }
+    }
+
+    // Re-encrypts a renamed app's variables under its new name; nothing changes on failure
+    pub fn rename_app(&self, from: &str, to: &str) -> Result<(), String> {
+        let mut entries = self.entries.write().unwrap();
+        let Some(vars) = entries.get(from) else { return Ok(()) };
+        let mut renamed = BTreeMap::new();
+        for (name, var) in vars {
+            let plaintext = self.decrypt(from, name, &var.value)?;
+            let value = self.encrypt(to, name, &plaintext)?;
+            renamed.insert(name.clone(), StoredVar { value, ..var.clone() });
+        }
+        entries.remove(from);
+        entries.insert(to.to_string(), renamed);
+        save_entries(&self.path, &entries).map_err(|e| format!("Failed to save: {}", e))
+    }
+
+    // The app's variables still encrypted, for backups
+    pub fn sealed(&self, app_name: &str) -> BTreeMap<String, StoredVar> {
+        self.entries.read().unwrap().get(app_name).cloned().unwrap_or_default()
+    }
+
+    // Adds variables from a backup of `source_app`, re-encrypted for `app_name`.
+    // Returns the names that don't decrypt here (made with another secrets key).
+    pub fn restore_sealed(&self, app_name: &str, source_app: &str, vars: &BTreeMap<String, StoredVar>) -> Result<Vec<String>, String> {
+        let mut restored = BTreeMap::new();
+        let mut unreadable = Vec::new();
+        for (name, var) in vars {
+            match self.decrypt(source_app, name, &var.value) {
+                Ok(plaintext) => {
+                    let value = self.encrypt(app_name, name, &plaintext)?;
+                    restored.insert(name.clone(), StoredVar { value, ..var.clone() });
+                }
+                Err(_) => unreadable.push(name.clone()),
+            }
+        }
+        if !restored.is_empty() {
+            let mut entries = self.entries.write().unwrap();
+            entries.entry(app_name.to_string()).or_default().extend(restored);
+            save_entries(&self.path, &entries).map_err(|e| format!("Failed to save: {}", e))?;
+        }
+        Ok(unreadable)
+    }
+
+    // Decrypted NAME=value pairs for container creation
+    pub fn env_for(&self, app_name: &str) -> Result<Vec<String>, String> {
+        let entries = self.entries.read().unwrap();
+        let Some(vars) = entries.get(app_name) else { return Ok(Vec::new()) };
+        vars.iter()
+            .map(|(name, var)| Ok(format!("{}={}", name, self.decrypt(app_name, name, &var.value)?)))
+            .collect()
+    }
+}
+
+// ── Handlers ──
+
+#[derive(Deserialize)]
+pub struct SetEnvVarRequest {
+    value: String,
+    #[serde(default)]
+    secret: bool,
+}
+
+fn bad_request(message: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": message}))).into_response()
+}
+
+fn check_app(app_name: &str) -> Option<Response> {
+    if app_name.contains('/') || app_name.contains("..") {
+        return Some(bad_request("Invalid app name"));
+    }
+    if !std::path::Path::new(&format!("/apps/{}", app_name)).exists() {
+        return Some((StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "App not found"}))).into_response());
+    }
+    None
+}
+
+pub async fn list_env(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> Response {
+    if let Some(resp) = check_app(&app_name) {
+        return resp;
+    }
+    Json(state.secrets.list(&app_name)).into_response()
+}
+
+pub async fn set_env(
+    State(state): State<Arc<AppState>>,
+    Path((app_name, name)): Path<(String, String)>,
+    Json(body): Json<SetEnvVarRequest>,
+) -> Response {
+    if let Some(resp) = check_app(&app_name) {
+        return resp;
+    }
+    if !valid_env_name(&name) {
+        return bad_request(&format!("'{}' is not a valid variable name", name));
+    }
+    if name == "APP_NAME" {
+        return bad_request("APP_NAME is set by the manager and cannot be overridden");
+    }
+    match state.secrets.set(&app_name, &name, &body.value, body.secret) {
+        Ok(view) => Json(serde_json::json!({"status": "saved", "var": view})).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+}
+
+pub async fn delete_env(
+    State(state): State<Arc<AppState>>,
+    Path((app_name, name)): Path<(String, String)>,
+) -> Response {
+    if let Some(resp) = check_app(&app_name) {
+        return resp;
+    }
+    match state.secrets.remove(&app_name, &name) {
+        Ok(true) => Json(serde_json::json!({"status": "deleted"})).into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, Json(serde_json::json!({"error": "Variable not found"}))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({"error": e}))).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    // A store file of its own under the temp dir, removed when dropped
+    struct TempFile(String);
+
+    impl TempFile {
+        fn new() -> Self {
+            let n = NEXT.fetch_add(1, Ordering::SeqCst);
+            TempFile(std::env::temp_dir().join(format!("app-env-test-{}-{}.json", std::process::id(), n)).display().to_string())
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+            let _ = std::fs::remove_file(format!("{}.tmp", self.0));
+        }
+    }
+
+    #[test]
+    fn values_round_trip_and_persist() {
+        let file = TempFile::new();
+        let store = SecretStore::open(&file.0, "key").unwrap();
+        store.set("demo", "LOG_LEVEL", "debug", false).unwrap();
+        store.set("demo", "TOKEN", "s3cret=with=equals", true).unwrap();
+        assert_eq!(store.env_for("demo").unwrap(), vec!["LOG_LEVEL=debug", "TOKEN=s3cret=with=equals"]);
+
+        // Secrets are never read back over the API, plain values are
+        let listed: Vec<(String, Option<String>)> = store.list("demo").into_iter().map(|v| (v.name, v.value)).collect();
+        assert_eq!(listed, vec![("LOG_LEVEL".to_string(), Some("debug".to_string())), ("TOKEN".to_string(), None)]);
+        // Nothing is stored in the clear
+        let raw = std::fs::read_to_string(&file.0).unwrap();
+        assert!(!raw.contains("debug") && !raw.contains("s3cret"));
+        assert!(!std::path::Path::new(&format!("{}.tmp", file.0)).exists());
+
+        let reopened = SecretStore::open(&file.0, "key").unwrap();
+        assert_eq!(reopened.env_for("demo").unwrap(), store.env_for("demo").unwrap());
+        let wrong_key = SecretStore::open(&file.0, "other key").unwrap();
+        assert!(wrong_key.env_for("demo").is_err());
+    }
+
+    #[test]
+    fn ciphertexts_are_bound_to_app_and_name() {
+        let file = TempFile::new();
+        let store = SecretStore::open(&file.0, "key").unwrap();
+        store.set("demo", "TOKEN", "s3cret", true).unwrap();
+        let sealed = store.sealed("demo").remove("TOKEN").unwrap();
+
+        assert_eq!(store.decrypt("demo", "TOKEN", &sealed.value).unwrap(), "s3cret");
+        assert!(store.decrypt("other", "TOKEN", &sealed.value).is_err());
+        assert!(store.decrypt("demo", "PASSWORD", &sealed.value).is_err());
+
+        // Swapped into another app's entry in the file, it no longer decrypts
+        store.entries.write().unwrap().entry("other".to_string()).or_default().insert("TOKEN".to_string(), sealed);
+        assert!(store.env_for("other").is_err());
+    }
+
+    #[test]
+    fn rename_re_encrypts_under_the_new_name() {
+        let file = TempFile::new();
+        let store = SecretStore::open(&file.0, "key").unwrap();
+        store.set("old", "TOKEN", "s3cret", true).unwrap();
+        let before = store.sealed("old")["TOKEN"].value.clone();
+
+        store.rename_app("old", "new").unwrap();
+        assert!(store.sealed("old").is_empty());
+        assert_eq!(store.env_for("new").unwrap(), vec!["TOKEN=s3cret"]);
+        assert_ne!(store.sealed("new")["TOKEN"].value, before);
+        // Renaming an app without variables is a no-op
+        store.rename_app("missing", "elsewhere").unwrap();
+        assert!(store.sealed("elsewhere").is_empty());
+    }
+
+    #[test]
+    fn restore_reports_values_sealed_with_another_key() {
+        let (ours, theirs) = (TempFile::new(), TempFile::new());
+        let source = SecretStore::open(&theirs.0, "key").unwrap();
+        source.set("demo", "TOKEN", "s3cret", true).unwrap();
+        let backup = source.sealed("demo");
+
+        // Same key: restored under the new name
+        let same = SecretStore::open(&ours.0, "key").unwrap();
+        assert!(same.restore_sealed("copy", "demo", &backup).unwrap().is_empty());
+        assert_eq!(same.env_for("copy").unwrap(), vec!["TOKEN=s3cret"]);
+
+        // Another hub's key: reported, nothing stored
+        let other = SecretStore::open(&ours.0, "other key").unwrap();
+        assert_eq!(other.restore_sealed("copy2", "demo", &backup).unwrap(), vec!["TOKEN".to_string()]);
+        assert!(other.sealed("copy2").is_empty());
+    }
+
+    #[test]
+    fn corrupt_store_is_refused() {
+        let file = TempFile::new();
+        std::fs::write(&file.0, "{\"demo\": {\"TOKEN\": ").unwrap();
+        assert!(SecretStore::open(&file.0, "key").err().unwrap().contains("corrupt"));
+        // Left as it was for the operator to fix
+        assert_eq!(std::fs::read_to_string(&file.0).unwrap(), "{\"demo\": {\"TOKEN\": ");
+    }
+}
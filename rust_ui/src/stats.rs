@@ -0,0 +1,198 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::now_iso8601;
+use crate::replicas;
+use crate::runtime::{ContainerRuntime, ContainerStats};
+use crate::AppState;
+
+// ── Resource usage stats ──
+//
+// A background collector samples every running app container each
+// STATS_INTERVAL_SECS (default 10) and keeps STATS_HISTORY_SECS (default one
+// hour) of samples per app. Network figures are turned into per-second rates
+// from the difference to the previous sample. Apps with several replicas get
+// one sample per interval with the figures summed over all replicas.
+
+const DEFAULT_INTERVAL_SECS: u64 = 10;
+const DEFAULT_HISTORY_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsPoint {
+    pub at: String,
+    pub cpu_percent: f64,
+    pub memory_bytes: u64,
+    pub memory_limit: u64,
+    pub pids: u64,
+    // Bytes per second since the previous sample
+    pub net_rx_rate: f64,
+    pub net_tx_rate: f64,
+}
+
+struct Sample {
+    taken: Instant,
+    net_rx_bytes: u64,
+    net_tx_bytes: u64,
+    point: StatsPoint,
+}
+
+// Summed current usage of a set of apps
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub cpu_percent: f64,
+    pub memory_bytes: u64,
+    pub net_rx_rate: f64,
+    pub net_tx_rate: f64,
+}
+
+#[derive(Clone)]
+pub struct StatsCollector {
+    apps: Arc<RwLock<HashMap<String, VecDeque<Sample>>>>,
+    interval: Duration,
+    capacity: usize,
+}
+
+impl StatsCollector {
+    pub fn from_env() -> Self {
+        let secs = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s| *s >= 1)
+                .unwrap_or(default)
+        };
+        let interval = secs("STATS_INTERVAL_SECS", DEFAULT_INTERVAL_SECS);
+        let history = secs("STATS_HISTORY_SECS", DEFAULT_HISTORY_SECS);
+        StatsCollector {
+            apps: Arc::new(RwLock::new(HashMap::new())),
+            interval: Duration::from_secs(interval),
+            capacity: (history / interval).max(1) as usize,
+        }
+    }
+
+    // Latest sample, unless the app stopped being sampled
+    pub fn current(&self, app_name: &str) -> Option<StatsPoint> {
+        let apps = self.apps.read().unwrap();
+        let last = apps.get(app_name)?.back()?;
+        (last.taken.elapsed() < self.interval * 2).then(|| last.point.clone())
+    }
+
+    // Oldest first
+    pub fn history(&self, app_name: &str) -> Vec<StatsPoint> {
+        let apps = self.apps.read().unwrap();
+        apps.get(app_name).map(|h| h.iter().map(|s| s.point.clone()).collect()).unwrap_or_default()
+    }
+
+    pub fn totals(&self, app_names: &[String]) -> UsageTotals {
+        app_names.iter().filter_map(|a| self.current(a)).fold(UsageTotals::default(), |mut t, p| {
+            t.cpu_percent = ((t.cpu_percent + p.cpu_percent) * 100.0).round() / 100.0;
+            t.memory_bytes += p.memory_bytes;
+            t.net_rx_rate += p.net_rx_rate;
+            t.net_tx_rate += p.net_tx_rate;
+            t
+        })
+    }
+
+    pub fn spawn(&self, runtime: Arc<dyn ContainerRuntime>) {
+        let collector = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(collector.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                collector.sample(&runtime).await;
+            }
+        });
+    }
+
+    async fn sample(&self, runtime: &Arc<dyn ContainerRuntime>) {
+        let Ok(containers) = runtime.list_containers(false).await else { return };
+
+        // Docker takes about a second per stats call, so query all apps at once
+        let mut tasks = tokio::task::JoinSet::new();
+        for c in containers {
+            // Skips {app}_green while a blue/green rollout is in progress
+            let Some(app_name) = replicas::app_of(&c.name, &c.labels).map(|a| a.to_string()) else { continue };
+            let runtime = runtime.clone();
+            tasks.spawn(async move {
+                let stats = runtime.container_stats(&c.name).await;
+                (app_name, stats)
+            });
+        }
+        let mut per_app: HashMap<String, ContainerStats> = HashMap::new();
+        while let Some(Ok((app_name, stats))) = tasks.join_next().await {
+            let Ok(stats) = stats else { continue };
+            let sum = per_app.entry(app_name).or_default();
+            sum.cpu_percent += stats.cpu_percent;
+            sum.memory_bytes += stats.memory_bytes;
+            sum.memory_limit += stats.memory_limit;
+            sum.net_rx_bytes += stats.net_rx_bytes;
+            sum.net_tx_bytes += stats.net_tx_bytes;
+            sum.pids += stats.pids;
+        }
+
+        for (app_name, stats) in per_app {
+            let taken = Instant::now();
+            let mut apps = self.apps.write().unwrap();
+            let history = apps.entry(app_name).or_default();
+
+            // Counters reset when the container is recreated
+            let rate = |now: u64, before: Option<(u64, Instant)>| match before {
+                Some((prev, at)) if now >= prev => {
+                    (now - prev) as f64 / taken.duration_since(at).as_secs_f64().max(1.0)
+                }
+                _ => 0.0,
+            };
+            let prev = history.back();
+            let point = StatsPoint {
+                at: now_iso8601(),
+                cpu_percent: (stats.cpu_percent * 100.0).round() / 100.0,
+                memory_bytes: stats.memory_bytes,
+                memory_limit: stats.memory_limit,
+                pids: stats.pids,
+                net_rx_rate: rate(stats.net_rx_bytes, prev.map(|p| (p.net_rx_bytes, p.taken))).round(),
+                net_tx_rate: rate(stats.net_tx_bytes, prev.map(|p| (p.net_tx_bytes, p.taken))).round(),
+            };
+            history.push_back(Sample {
+                taken,
+                net_rx_bytes: stats.net_rx_bytes,
+                net_tx_bytes: stats.net_tx_bytes,
+                point,
+            });
+            while history.len() > self.capacity {
+                history.pop_front();
+            }
+        }
+
+        // History of stopped or deleted apps ages out like everything else
+        let window = self.interval * self.capacity as u32;
+        let mut apps = self.apps.write().unwrap();
+        for history in apps.values_mut() {
+            while history.front().is_some_and(|s| s.taken.elapsed() > window) {
+                history.pop_front();
+            }
+        }
+        apps.retain(|_, history| !history.is_empty());
+    }
+}
+
+// ── Handlers ──
+
+pub async fn get_stats(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "app": app_name,
+        "interval_secs": state.stats.interval.as_secs(),
+        "current": state.stats.current(&app_name),
+        "history": state.stats.history(&app_name),
+    }))
+}
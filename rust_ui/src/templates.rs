@@ -0,0 +1,261 @@
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+// ── Scaffold templates ──
+//
+// Each subdirectory of TEMPLATES_DIR (default /manager/templates) is a
+// template; its name is the template ID. template.toml describes it:
+//
+//   name = "Flask"
+//   language = "python"
+//   description = "Flask app with code-server"
+//
+//   [[variables]]
+//   name = "message"
+//   description = "Greeting returned by GET /"
+//   default = "Hello!"        # omit to make the variable required
+//
+// Every other file is copied into /apps/{app}, with {{name}} (or {{ name }})
+// replaced by the variable's value in text files. {{app_name}} is always
+// available; other {{...}} sequences are left alone. Replacement is a single
+// pass, so a value containing {{...}} is written as it is. Values end up
+// inside string literals in the generated code, so they must be plain text:
+// quotes, backslashes and control characters (newlines included) are refused.
+// The directory is read on every request, so templates can be added or
+// edited without a restart.
+
+const DEFAULT_TEMPLATES_DIR: &str = "/manager/templates";
+const METADATA_FILE: &str = "template.toml";
+// Used by `POST /api/create/{app}` without ?template=
+pub const DEFAULT_TEMPLATE: &str = "fastapi";
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Variable {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Metadata {
+    name: String,
+    language: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    variables: Vec<Variable>,
+}
+
+#[derive(Serialize)]
+pub struct Template {
+    id: String,
+    name: String,
+    language: String,
+    description: String,
+    variables: Vec<Variable>,
+}
+
+fn templates_dir() -> String {
+    std::env::var("TEMPLATES_DIR").unwrap_or_else(|_| DEFAULT_TEMPLATES_DIR.to_string())
+}
+
+fn valid_variable_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn load(id: &str) -> Result<Template, String> {
+    let path = Path::new(&templates_dir()).join(id).join(METADATA_FILE);
+    let src = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let metadata: Metadata = toml::from_str(&src).map_err(|e| format!("{}: {}", path.display(), e.message()))?;
+    for variable in &metadata.variables {
+        if !valid_variable_name(&variable.name) || variable.name == "app_name" {
+            return Err(format!("{}: invalid variable name '{}'", path.display(), variable.name));
+        }
+    }
+    Ok(Template {
+        id: id.to_string(),
+        name: metadata.name,
+        language: metadata.language,
+        description: metadata.description,
+        variables: metadata.variables,
+    })
+}
+
+// Templates sorted by ID, plus the errors of any that failed to load
+fn load_all() -> (Vec<Template>, Vec<String>) {
+    let mut ids: Vec<String> = std::fs::read_dir(templates_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().join(METADATA_FILE).is_file())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.sort();
+    let mut templates = Vec::new();
+    let mut errors = Vec::new();
+    for id in ids {
+        match load(&id) {
+            Ok(t) => templates.push(t),
+            Err(e) => errors.push(e),
+        }
+    }
+    (templates, errors)
+}
+
+// Resolves the value of every variable: given, else the default; unknown
+// and missing ones are errors
+fn resolve(template: &Template, app_name: &str, given: &HashMap<String, String>) -> Result<Vec<(String, String)>, String> {
+    if let Some(unknown) = given.keys().find(|k| !template.variables.iter().any(|v| &v.name == *k)) {
+        return Err(format!("Template '{}' has no variable '{}'", template.id, unknown));
+    }
+    let mut values = vec![("app_name".to_string(), app_name.to_string())];
+    for variable in &template.variables {
+        let value = given.get(&variable.name).or(variable.default.as_ref())
+            .ok_or_else(|| format!("Variable '{}' is required by template '{}'", variable.name, template.id))?;
+        if !plain_text(value) {
+            return Err(format!(
+                "Variable '{}' must be plain text without quotes, backslashes or control characters",
+                variable.name
+            ));
+        }
+        values.push((variable.name.clone(), value.clone()));
+    }
+    Ok(values)
+}
+
+fn plain_text(value: &str) -> bool {
+    !value.chars().any(|c| c == '"' || c == '\'' || c == '\\' || c == '`' || c.is_control())
+}
+
+// Replaces each {{name}} / {{ name }} in one pass over `text`; inserted values
+// are never scanned again
+fn substitute(text: &str, values: &[(String, String)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start + 2..];
+        let value = tail.find("}}").and_then(|end| {
+            let inner = &tail[..end];
+            let name = inner.strip_prefix(' ').and_then(|n| n.strip_suffix(' ')).unwrap_or(inner);
+            values.iter().find(|(n, _)| n == name).map(|(_, value)| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                out.push_str(value);
+                rest = &tail[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = tail;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn copy_dir(from: &Path, to: &Path, values: &[(String, String)], top: bool) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let src = entry.path();
+        let dst = to.join(entry.file_name());
+        if top && entry.file_name() == METADATA_FILE {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir(&src, &dst, values, false)?;
+            continue;
+        }
+        // Binary files are copied as they are
+        match String::from_utf8(std::fs::read(&src)?) {
+            Ok(text) => std::fs::write(&dst, substitute(&text, values))?,
+            Err(e) => std::fs::write(&dst, e.into_bytes())?,
+        }
+        std::fs::set_permissions(&dst, entry.metadata()?.permissions())?;
+    }
+    Ok(())
+}
+
+// Writes the template's files into /apps/{app}, which must not exist yet
+pub fn scaffold(app_name: &str, id: &str, given: &HashMap<String, String>) -> Result<(), String> {
+    if !crate::valid_app_name(id) || !Path::new(&templates_dir()).join(id).join(METADATA_FILE).is_file() {
+        return Err(format!("Template '{}' not found", id));
+    }
+    let template = load(id)?;
+    let values = resolve(&template, app_name, given)?;
+    let app_dir = format!("/apps/{}", app_name);
+    if let Err(e) = copy_dir(&Path::new(&templates_dir()).join(id), Path::new(&app_dir), &values, true) {
+        let _ = std::fs::remove_dir_all(&app_dir);
+        return Err(format!("Failed to write app files: {}", e));
+    }
+    Ok(())
+}
+
+// ── Handlers ──
+
+pub async fn list_templates() -> Json<serde_json::Value> {
+    let (templates, errors) = load_all();
+    Json(serde_json::json!({
+        "default": DEFAULT_TEMPLATE,
+        "templates": templates,
+        "errors": errors,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn substitutes_in_one_pass() {
+        let values = values(&[("app_name", "demo"), ("message", "{{app_name}}"), ("port", "80")]);
+        let cases = [
+            ("name = \"{{app_name}}\"", "name = \"demo\""),
+            ("{{ app_name }}:{{port}}", "demo:80"),
+            // Values aren't expanded again
+            ("msg = {{message}}", "msg = {{app_name}}"),
+            // Unknown names, other spacing and stray braces are left alone
+            ("{{unknown}} {{  port  }} {{port", "{{unknown}} {{  port  }} {{port"),
+            ("{{{{port}}}}", "{{80}}"),
+            ("no placeholders", "no placeholders"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(substitute(text, &values), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn refuses_values_that_are_not_plain_text() {
+        let template = Template {
+            id: "flask".to_string(),
+            name: "Flask".to_string(),
+            language: "python".to_string(),
+            description: String::new(),
+            variables: vec![Variable { name: "message".to_string(), description: String::new(), default: Some("Hello!".to_string()) }],
+        };
+        let resolve_with = |value: &str| {
+            resolve(&template, "demo", &HashMap::from([("message".to_string(), value.to_string())]))
+        };
+        assert_eq!(resolve_with("Hi there, {{app_name}}!").unwrap()[1].1, "Hi there, {{app_name}}!");
+        for bad in ["say \"hi\"", "it's", "back\\slash", "two\nlines", "tab\there", "`cmd`"] {
+            assert!(resolve_with(bad).is_err(), "{:?}", bad);
+        }
+        assert_eq!(resolve(&template, "demo", &HashMap::new()).unwrap()[1].1, "Hello!");
+        assert!(resolve_with("x").is_ok());
+    }
+}
@@ -0,0 +1,470 @@
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::runtime::{ContainerRuntime, RuntimeError};
+use crate::{now_iso8601, AppState};
+
+// ── Interactive shell (WebSocket exec) ──
+//
+// GET /api/apps/{app}/exec upgrades to a WebSocket attached to a TTY in the
+// app container. Binary frames from the client are written to the shell's
+// stdin; text frames are JSON control messages:
+//   {"type": "stdin", "data": "ls\r"}
+//   {"type": "resize", "cols": 120, "rows": 40}
+// Shell output comes back as binary frames. Sessions close after
+// EXEC_IDLE_TIMEOUT_SECS without client input, and every session's start and
+// end are appended to /apps/exec_audit.log. Apps with several replicas open
+// the shell in the first running one unless `replica` names another.
+
+const EXEC_AUDIT_PATH: &str = "/apps/exec_audit.log";
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 900;
+const DEFAULT_SHELL: &str = "if command -v bash >/dev/null; then exec bash -l; else exec sh; fi";
+// Application-defined close code for idle sessions
+const CLOSE_IDLE: u16 = 4000;
+
+pub fn idle_timeout_from_env() -> Duration {
+    let secs = std::env::var("EXEC_IDLE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|s| *s >= 1)
+        .unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+fn default_cols() -> u16 {
+    80
+}
+
+fn default_rows() -> u16 {
+    24
+}
+
+#[derive(Deserialize)]
+pub struct ExecQuery {
+    // Shell command line; defaults to bash (or sh)
+    cmd: Option<String>,
+    #[serde(default = "default_cols")]
+    cols: u16,
+    #[serde(default = "default_rows")]
+    rows: u16,
+    // Container name of a single replica
+    replica: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Stdin { data: String },
+    Resize { cols: u16, rows: u16 },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct AuditRecord {
+    session: String,
+    app: String,
+    // Replica the shell ran in; older records only have the app
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    container: String,
+    // "start" or "end"
+    event: String,
+    at: String,
+    client: String,
+    user_agent: String,
+    command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes_in: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes_out: Option<u64>,
+}
+
+fn append_audit(record: &AuditRecord) {
+    let line = match serde_json::to_string(record) {
+        Ok(l) => l,
+        Err(_) => return,
+    };
+    println!("exec audit: {}", line);
+    let file = std::fs::OpenOptions::new().create(true).append(true).open(EXEC_AUDIT_PATH);
+    if let Err(e) = file.and_then(|mut f| writeln!(f, "{}", line)) {
+        eprintln!("Failed to write exec audit log: {}", e);
+    }
+}
+
+// Cookies ride along on cross-site WebSocket handshakes, so only accept
+// upgrades initiated from a page served by this host
+fn same_origin(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get("origin").and_then(|v| v.to_str().ok()) else {
+        // Non-browser clients don't send Origin
+        return true;
+    };
+    let host = headers.get("host").and_then(|v| v.to_str().ok()).unwrap_or_default();
+    origin.split_once("://").is_some_and(|(_, rest)| rest == host)
+}
+
+pub async fn exec_shell(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+    Query(query): Query<ExecQuery>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let error = |status: StatusCode, msg: &str| (status, Json(serde_json::json!({"error": msg}))).into_response();
+    if !same_origin(&headers) {
+        return error(StatusCode::FORBIDDEN, "Cross-origin exec sessions are not allowed");
+    }
+    let container = match &query.replica {
+        Some(replica) if state.status.replica_names(&app_name).contains(replica) => replica.clone(),
+        Some(_) => return error(StatusCode::NOT_FOUND, "Replica not found"),
+        None => state.status.replicas(&app_name)
+            .into_iter()
+            .find(|r| r.running)
+            .map(|r| r.name)
+            .unwrap_or_else(|| app_name.clone()),
+    };
+    match state.runtime.inspect_container(&container).await {
+        Ok(details) if details.running => {}
+        Ok(_) => return error(StatusCode::CONFLICT, "Container is not running"),
+        Err(RuntimeError::NotFound(_)) => return error(StatusCode::NOT_FOUND, "Container not found"),
+        Err(e) => return error(StatusCode::BAD_GATEWAY, &e.to_string()),
+    }
+
+    let audit = AuditRecord {
+        session: format!("{:016x}", rand::random::<u64>()),
+        app: app_name,
+        container,
+        event: "start".to_string(),
+        at: now_iso8601(),
+        client: addr.to_string(),
+        user_agent: headers
+            .get("user-agent")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string(),
+        command: query.cmd.clone().unwrap_or_else(|| DEFAULT_SHELL.to_string()),
+        reason: None,
+        duration_secs: None,
+        bytes_in: None,
+        bytes_out: None,
+    };
+    ws.on_upgrade(move |socket| async move {
+        run_session(state.runtime.as_ref(), state.exec_idle_timeout, socket, &query, audit, append_audit).await
+    })
+}
+
+// The frames a session sends and receives; tests stand in for the WebSocket
+trait Socket {
+    async fn recv(&mut self) -> Option<Result<Message, axum::Error>>;
+    async fn send(&mut self, msg: Message) -> Result<(), axum::Error>;
+}
+
+impl Socket for WebSocket {
+    async fn recv(&mut self) -> Option<Result<Message, axum::Error>> {
+        WebSocket::recv(self).await
+    }
+
+    async fn send(&mut self, msg: Message) -> Result<(), axum::Error> {
+        WebSocket::send(self, msg).await
+    }
+}
+
+async fn run_session(
+    runtime: &dyn ContainerRuntime,
+    idle_timeout: Duration,
+    mut socket: impl Socket,
+    query: &ExecQuery,
+    mut audit: AuditRecord,
+    record: impl Fn(&AuditRecord),
+) {
+    let cmd = vec!["sh".to_string(), "-c".to_string(), audit.command.clone()];
+    let session = match runtime.exec_interactive(&audit.container, &cmd, query.cols, query.rows).await {
+        Ok(s) => s,
+        Err(e) => {
+            let _ = socket.send(Message::Close(Some(CloseFrame {
+                code: axum::extract::ws::close_code::ERROR,
+                reason: format!("exec failed: {}", e).into(),
+            }))).await;
+            return;
+        }
+    };
+    record(&audit);
+
+    let started = Instant::now();
+    let mut last_input = Instant::now();
+    let (mut bytes_in, mut bytes_out) = (0u64, 0u64);
+    let (mut output, mut stdin) = tokio::io::split(session.io);
+    let mut buf = vec![0u8; 8192];
+
+    let reason = loop {
+        tokio::select! {
+            msg = socket.recv() => {
+                let data = match msg {
+                    Some(Ok(Message::Binary(data))) => data.to_vec(),
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(ClientMessage::Stdin { data }) => data.into_bytes(),
+                        Ok(ClientMessage::Resize { cols, rows }) => {
+                            let _ = runtime.resize_exec(&session.id, cols, rows).await;
+                            last_input = Instant::now();
+                            continue;
+                        }
+                        Err(_) => continue,
+                    },
+                    Some(Ok(Message::Close(_))) | None => break "client disconnected",
+                    Some(Err(_)) => break "connection error",
+                    Some(Ok(_)) => continue,
+                };
+                last_input = Instant::now();
+                bytes_in += data.len() as u64;
+                if stdin.write_all(&data).await.is_err() {
+                    break "shell exited";
+                }
+            }
+            n = output.read(&mut buf) => {
+                let n = match n {
+                    Ok(0) | Err(_) => {
+                        let _ = socket.send(Message::Close(Some(CloseFrame {
+                            code: axum::extract::ws::close_code::NORMAL,
+                            reason: "shell exited".into(),
+                        }))).await;
+                        break "shell exited";
+                    }
+                    Ok(n) => n,
+                };
+                bytes_out += n as u64;
+                if socket.send(Message::Binary(buf[..n].to_vec().into())).await.is_err() {
+                    break "client disconnected";
+                }
+            }
+            _ = tokio::time::sleep_until((last_input + idle_timeout).into()) => {
+                let _ = socket.send(Message::Close(Some(CloseFrame {
+                    code: CLOSE_IDLE,
+                    reason: "idle timeout".into(),
+                }))).await;
+                break "idle timeout";
+            }
+        }
+    };
+    // Closing stdin ends the shell (EOF) if it is still running
+    let _ = stdin.shutdown().await;
+
+    audit.event = "end".to_string();
+    audit.at = now_iso8601();
+    audit.reason = Some(reason.to_string());
+    audit.duration_secs = Some(started.elapsed().as_secs());
+    audit.bytes_in = Some(bytes_in);
+    audit.bytes_out = Some(bytes_out);
+    record(&audit);
+}
+
+// Audit records for an app, newest first
+pub async fn list_exec_audit(Path(app_name): Path<String>) -> Json<serde_json::Value> {
+    let records: Vec<AuditRecord> = std::fs::read_to_string(EXEC_AUDIT_PATH)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str::<AuditRecord>(l).ok())
+        .filter(|r| r.app == app_name)
+        .rev()
+        .collect();
+    Json(serde_json::json!({ "app": app_name, "sessions": records }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{BuildSpec, FakeRuntime, RunSpec};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use tokio::sync::mpsc;
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    // Client side of a session: frames sent to the shell, and frames received from it
+    struct TestSocket {
+        incoming: mpsc::Receiver<Message>,
+        outgoing: mpsc::Sender<Message>,
+    }
+
+    impl Socket for TestSocket {
+        async fn recv(&mut self) -> Option<Result<Message, axum::Error>> {
+            self.incoming.recv().await.map(Ok)
+        }
+
+        async fn send(&mut self, msg: Message) -> Result<(), axum::Error> {
+            self.outgoing.send(msg).await.map_err(|_| axum::Error::new("client gone"))
+        }
+    }
+
+    // A fake runtime with a running "demo" container
+    async fn runtime() -> FakeRuntime {
+        let context = std::env::temp_dir().join(format!("terminal-test-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::SeqCst)));
+        std::fs::create_dir_all(&context).unwrap();
+        std::fs::write(context.join("Dockerfile"), "FROM scratch\n").unwrap();
+        let runtime = FakeRuntime::default();
+        let (sink, _) = mpsc::unbounded_channel();
+        let spec = BuildSpec { tag: "demo:latest".to_string(), context_dir: context.display().to_string(), network_mode: None };
+        runtime.build_image(&spec, &sink).await.unwrap();
+        let _ = std::fs::remove_dir_all(&context);
+        runtime.run_container(&RunSpec::new("demo", "demo:latest")).await.unwrap();
+        runtime
+    }
+
+    fn start_record(container: &str) -> AuditRecord {
+        AuditRecord {
+            session: "0000000000000001".to_string(),
+            app: "demo".to_string(),
+            container: container.to_string(),
+            event: "start".to_string(),
+            at: now_iso8601(),
+            client: "127.0.0.1:50000".to_string(),
+            user_agent: "test".to_string(),
+            command: "sh".to_string(),
+            reason: None,
+            duration_secs: None,
+            bytes_in: None,
+            bytes_out: None,
+        }
+    }
+
+    fn query() -> ExecQuery {
+        ExecQuery { cmd: None, cols: default_cols(), rows: default_rows(), replica: None }
+    }
+
+    // Runs a session against `client`, returning the audit records it wrote
+    async fn session<F: Future<Output = ()>>(
+        runtime: &FakeRuntime,
+        container: &str,
+        idle_timeout: Duration,
+        client: impl FnOnce(mpsc::Sender<Message>, mpsc::Receiver<Message>) -> F,
+    ) -> Vec<AuditRecord> {
+        let (to_shell, incoming) = mpsc::channel(16);
+        let (outgoing, from_shell) = mpsc::channel(16);
+        let records = Mutex::new(Vec::new());
+        let socket = TestSocket { incoming, outgoing };
+        let record = |r: &AuditRecord| records.lock().unwrap().push(r.clone());
+        let query = query();
+        tokio::join!(
+            run_session(runtime, idle_timeout, socket, &query, start_record(container), record),
+            client(to_shell, from_shell),
+        );
+        records.into_inner().unwrap()
+    }
+
+    // Reads binary frames until `expected` has arrived
+    async fn read_output(from_shell: &mut mpsc::Receiver<Message>, expected: &str) {
+        let mut output = Vec::new();
+        while output.len() < expected.len() {
+            match from_shell.recv().await {
+                Some(Message::Binary(data)) => output.extend_from_slice(&data),
+                other => panic!("expected output, got {:?}", other),
+            }
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    async fn close_frame(from_shell: &mut mpsc::Receiver<Message>) -> CloseFrame {
+        match from_shell.recv().await {
+            Some(Message::Close(Some(frame))) => frame,
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn relays_input_and_output() {
+        let runtime = runtime().await;
+        let records = session(&runtime, "demo", Duration::from_secs(5), |to_shell, mut from_shell| async move {
+            to_shell.send(Message::Binary(b"echo hi\r".to_vec().into())).await.unwrap();
+            read_output(&mut from_shell, "echo hi\r\n").await;
+            to_shell.send(Message::Text(r#"{"type": "resize", "cols": 120, "rows": 40}"#.into())).await.unwrap();
+            to_shell.send(Message::Text(r#"{"type": "stdin", "data": "ls\r"}"#.into())).await.unwrap();
+            read_output(&mut from_shell, "ls\r\n").await;
+        })
+        .await;
+
+        let [start, end] = records.as_slice() else { panic!("expected two records, got {}", records.len()) };
+        assert_eq!(start.event, "start");
+        assert_eq!(start.bytes_in, None);
+        assert_eq!(end.event, "end");
+        assert_eq!(end.session, start.session);
+        assert_eq!(end.reason.as_deref(), Some("client disconnected"));
+        assert_eq!(end.bytes_in, Some(11));
+        assert_eq!(end.bytes_out, Some(13));
+    }
+
+    #[tokio::test]
+    async fn closes_when_the_shell_exits() {
+        let runtime = runtime().await;
+        let records = session(&runtime, "demo", Duration::from_secs(5), |to_shell, mut from_shell| async move {
+            // Ctrl-D
+            to_shell.send(Message::Binary(vec![4].into())).await.unwrap();
+            let frame = close_frame(&mut from_shell).await;
+            assert_eq!(frame.code, axum::extract::ws::close_code::NORMAL);
+            assert_eq!(frame.reason, "shell exited");
+        })
+        .await;
+        assert_eq!(records.last().unwrap().reason.as_deref(), Some("shell exited"));
+        assert_eq!(records.last().unwrap().bytes_in, Some(1));
+    }
+
+    #[tokio::test]
+    async fn closes_idle_sessions() {
+        let runtime = runtime().await;
+        let records = session(&runtime, "demo", Duration::from_millis(100), |to_shell, mut from_shell| async move {
+            let frame = close_frame(&mut from_shell).await;
+            assert_eq!(frame.code, CLOSE_IDLE);
+            assert_eq!(frame.reason, "idle timeout");
+            // Stay connected, sending nothing, until the session gives up
+            drop(to_shell);
+        })
+        .await;
+        let end = records.last().unwrap();
+        assert_eq!(end.event, "end");
+        assert_eq!(end.reason.as_deref(), Some("idle timeout"));
+        assert_eq!((end.bytes_in, end.bytes_out), (Some(0), Some(0)));
+    }
+
+    #[tokio::test]
+    async fn failed_exec_is_not_audited() {
+        let runtime = runtime().await;
+        let records = session(&runtime, "missing", Duration::from_secs(5), |_to_shell, mut from_shell| async move {
+            let frame = close_frame(&mut from_shell).await;
+            assert_eq!(frame.code, axum::extract::ws::close_code::ERROR);
+            assert!(frame.reason.starts_with("exec failed"), "{}", frame.reason);
+        })
+        .await;
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn only_same_origin_upgrades_are_allowed() {
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.insert(*name, value.parse().unwrap());
+            }
+            headers
+        };
+        assert!(same_origin(&headers(&[("host", "hub.example.com")])));
+        assert!(same_origin(&headers(&[("host", "hub.example.com"), ("origin", "https://hub.example.com")])));
+        assert!(same_origin(&headers(&[("host", "localhost:8081"), ("origin", "http://localhost:8081")])));
+        assert!(!same_origin(&headers(&[("host", "hub.example.com"), ("origin", "https://evil.example.com")])));
+        assert!(!same_origin(&headers(&[("host", "localhost:8081"), ("origin", "http://localhost:9999")])));
+        assert!(!same_origin(&headers(&[("host", "hub.example.com"), ("origin", "null")])));
+        assert!(!same_origin(&headers(&[("origin", "https://hub.example.com")])));
+    }
+}
@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::runtime::RunSpec;
+
+// ── Traefik route model ──
+//
+// Every app container gets an API router (/{app}, behind ForwardAuth) and an
+// IDE router (/{app}-ide). Apps may declare extra routers under their API
+// prefix, e.g. a websocket endpoint or static files served on another port.
+
+pub const API_PORT: u16 = 80;
+pub const IDE_PORT: u16 = 8000;
+
+fn default_true() -> bool {
+    true
+}
+
+// Extra router declared per app, mounted at /{app}{path}
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExtraRoute {
+    pub name: String,
+    pub path: String,
+    pub port: u16,
+    #[serde(default = "default_true")]
+    pub auth: bool,
+    #[serde(default = "default_true")]
+    pub strip_prefix: bool,
+}
+
+impl ExtraRoute {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty()
+            || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!("route name '{}' must be non-empty and use only [A-Za-z0-9_-]", self.name));
+        }
+        if self.name == "ide" || self.name == "auth" || self.name == "strip" {
+            return Err(format!("route name '{}' is reserved", self.name));
+        }
+        if !self.path.starts_with('/') || self.path.len() < 2 || self.path.contains('`') {
+            return Err(format!("route '{}': path must start with '/' and not be empty", self.name));
+        }
+        if self.port == 0 {
+            return Err(format!("route '{}': port must be between 1 and 65535", self.name));
+        }
+        Ok(())
+    }
+}
+
+pub fn validate_routes(routes: &[ExtraRoute]) -> Result<(), String> {
+    for (i, route) in routes.iter().enumerate() {
+        route.validate()?;
+        if routes[..i].iter().any(|r| r.name == route.name) {
+            return Err(format!("duplicate route name '{}'", route.name));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+struct RouterSpec {
+    // Traefik router and service name
+    name: String,
+    prefix: String,
+    port: u16,
+    middlewares: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RouteSpec {
+    app_name: String,
+    auth_address: String,
+    api_port: u16,
+    ide_port: u16,
+    extra: Vec<ExtraRoute>,
+}
+
+impl RouteSpec {
+    pub fn for_app(app_name: &str, manager_ip: &str) -> Self {
+        RouteSpec {
+            app_name: app_name.to_string(),
+            auth_address: format!("http://{}:8081/api/verify", manager_ip),
+            api_port: API_PORT,
+            ide_port: IDE_PORT,
+            extra: Vec::new(),
+        }
+    }
+
+    pub fn with_ports(mut self, api_port: u16, ide_port: u16) -> Self {
+        self.api_port = api_port;
+        self.ide_port = ide_port;
+        self
+    }
+
+    pub fn with_extra_routes(mut self, routes: &[ExtraRoute]) -> Self {
+        self.extra.extend(routes.iter().cloned());
+        self
+    }
+
+    fn auth_middleware(&self) -> String {
+        format!("{}-auth", self.app_name)
+    }
+
+    fn strip_middleware(&self) -> String {
+        format!("{}-strip", self.app_name)
+    }
+
+    fn ide_strip_middleware(&self) -> String {
+        format!("{}-ide-strip", self.app_name)
+    }
+
+    fn routers(&self) -> Vec<RouterSpec> {
+        let app = &self.app_name;
+        let mut routers = vec![
+            // Chain: auth then strip prefix
+            RouterSpec {
+                name: app.clone(),
+                prefix: format!("/{}", app),
+                port: self.api_port,
+                middlewares: vec![self.auth_middleware(), self.strip_middleware()],
+            },
+            // IDE router (no ForwardAuth — IDE has its own password)
+            RouterSpec {
+                name: format!("{}-ide", app),
+                prefix: format!("/{}-ide", app),
+                port: self.ide_port,
+                middlewares: vec![self.ide_strip_middleware()],
+            },
+        ];
+        for route in &self.extra {
+            let mut middlewares = Vec::new();
+            if route.auth {
+                middlewares.push(self.auth_middleware());
+            }
+            if route.strip_prefix {
+                middlewares.push(self.strip_middleware());
+            }
+            // App names never contain '_', so these can't clash with another app's routers
+            routers.push(RouterSpec {
+                name: format!("{}_route_{}", app, route.name),
+                prefix: format!("/{}{}", app, route.path.trim_end_matches('/')),
+                port: route.port,
+                middlewares,
+            });
+        }
+        routers
+    }
+
+    pub fn labels(&self) -> BTreeMap<String, String> {
+        let app = &self.app_name;
+        let mut labels = BTreeMap::new();
+        labels.insert("traefik.enable".to_string(), "true".to_string());
+
+        // Shared middlewares
+        // ForwardAuth is always attached — verify endpoint handles "none" as passthrough
+        let auth = self.auth_middleware();
+        labels.insert(format!("traefik.http.middlewares.{}.forwardauth.address", auth), self.auth_address.clone());
+        labels.insert(format!("traefik.http.middlewares.{}.forwardauth.authRequestHeaders", auth), "X-API-Key,Authorization".to_string());
+        labels.insert(format!("traefik.http.middlewares.{}.forwardauth.authResponseHeaders", auth), "X-Forwarded-User".to_string());
+        labels.insert(
+            format!("traefik.http.middlewares.{}.stripprefix.prefixes", self.strip_middleware()),
+            format!("/{}/, /{}", app, app),
+        );
+        labels.insert(
+            format!("traefik.http.middlewares.{}.stripprefix.prefixes", self.ide_strip_middleware()),
+            format!("/{}-ide", app),
+        );
+
+        for router in self.routers() {
+            let r = &router.name;
+            labels.insert(format!("traefik.http.routers.{}.rule", r), format!("PathPrefix(`{}`)", router.prefix));
+            labels.insert(format!("traefik.http.routers.{}.service", r), r.clone());
+            if !router.middlewares.is_empty() {
+                labels.insert(format!("traefik.http.routers.{}.middlewares", r), router.middlewares.join(","));
+            }
+            labels.insert(format!("traefik.http.services.{}.loadbalancer.server.port", r), router.port.to_string());
+        }
+        // Each replica runs its own code-server; an IDE session must stay on one
+        labels.insert(format!("traefik.http.services.{}-ide.loadbalancer.sticky.cookie", app), "true".to_string());
+        labels
+    }
+
+    pub fn apply(&self, spec: &mut RunSpec) {
+        spec.labels.extend(self.labels());
+    }
+
+    // File-provider config for an app whose container is stopped: Traefik
+    // forgets the label-based routers, so a lowest-priority stand-in for the
+    // API router keeps its ForwardAuth (which wakes the app) and then proxies
+    // to the container by name once it is back on the network.
+    pub fn wake_config(&self) -> serde_json::Value {
+        let app = &self.app_name;
+        let name = format!("{}-wake", app);
+        let auth = format!("{}-auth", name);
+        let strip = format!("{}-strip", name);
+        serde_json::json!({
+            "routers": {
+                &name: {
+                    // Unlike the API router, must not catch /{app}-ide
+                    "rule": format!("Path(`/{0}`) || PathPrefix(`/{0}/`)", app),
+                    "priority": 1,
+                    "service": &name,
+                    "middlewares": [&auth, &strip],
+                },
+            },
+            "middlewares": {
+                &auth: {
+                    "forwardAuth": {
+                        "address": &self.auth_address,
+                        "authRequestHeaders": ["X-API-Key", "Authorization"],
+                        "authResponseHeaders": ["X-Forwarded-User"],
+                    },
+                },
+                &strip: { "stripPrefix": { "prefixes": [format!("/{}/", app), format!("/{}", app)] } },
+            },
+            "services": {
+                &name: { "loadBalancer": { "servers": [{ "url": format!("http://{}:{}", app, self.api_port) }] } },
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(name: &str, path: &str, port: u16, auth: bool, strip_prefix: bool) -> ExtraRoute {
+        ExtraRoute { name: name.into(), path: path.into(), port, auth, strip_prefix }
+    }
+
+    fn label<'a>(labels: &'a BTreeMap<String, String>, key: &str) -> Option<&'a str> {
+        labels.get(key).map(String::as_str)
+    }
+
+    #[test]
+    fn labels_for_api_and_ide_routers() {
+        let labels = RouteSpec::for_app("demo", "10.0.0.2").with_ports(3000, 8080).labels();
+        assert_eq!(label(&labels, "traefik.enable"), Some("true"));
+        assert_eq!(label(&labels, "traefik.http.routers.demo.rule"), Some("PathPrefix(`/demo`)"));
+        assert_eq!(label(&labels, "traefik.http.routers.demo.service"), Some("demo"));
+        assert_eq!(label(&labels, "traefik.http.routers.demo.middlewares"), Some("demo-auth,demo-strip"));
+        assert_eq!(label(&labels, "traefik.http.services.demo.loadbalancer.server.port"), Some("3000"));
+        assert_eq!(
+            label(&labels, "traefik.http.middlewares.demo-auth.forwardauth.address"),
+            Some("http://10.0.0.2:8081/api/verify")
+        );
+        assert_eq!(label(&labels, "traefik.http.middlewares.demo-strip.stripprefix.prefixes"), Some("/demo/, /demo"));
+
+        assert_eq!(label(&labels, "traefik.http.routers.demo-ide.rule"), Some("PathPrefix(`/demo-ide`)"));
+        assert_eq!(label(&labels, "traefik.http.routers.demo-ide.middlewares"), Some("demo-ide-strip"));
+        assert_eq!(label(&labels, "traefik.http.services.demo-ide.loadbalancer.server.port"), Some("8080"));
+        assert_eq!(label(&labels, "traefik.http.services.demo-ide.loadbalancer.sticky.cookie"), Some("true"));
+    }
+
+    #[test]
+    fn extra_routes_get_their_own_routers() {
+        let labels = RouteSpec::for_app("demo", "10.0.0.2")
+            .with_extra_routes(&[route("ws", "/ws/", 9000, true, false), route("static", "/static", 9001, false, true)])
+            .labels();
+        assert_eq!(label(&labels, "traefik.http.routers.demo_route_ws.rule"), Some("PathPrefix(`/demo/ws`)"));
+        assert_eq!(label(&labels, "traefik.http.routers.demo_route_ws.service"), Some("demo_route_ws"));
+        assert_eq!(label(&labels, "traefik.http.routers.demo_route_ws.middlewares"), Some("demo-auth"));
+        assert_eq!(label(&labels, "traefik.http.services.demo_route_ws.loadbalancer.server.port"), Some("9000"));
+        assert_eq!(label(&labels, "traefik.http.routers.demo_route_static.middlewares"), Some("demo-strip"));
+
+        let open = RouteSpec::for_app("demo", "10.0.0.2")
+            .with_extra_routes(&[route("raw", "/raw", 9002, false, false)])
+            .labels();
+        assert_eq!(label(&open, "traefik.http.routers.demo_route_raw.middlewares"), None);
+    }
+
+    #[test]
+    fn extra_routes_do_not_collide_with_other_apps() {
+        // App "a" with route "x" used to produce the same router as app "a-x"
+        let extra = RouteSpec::for_app("a", "10.0.0.2").with_extra_routes(&[route("x", "/x", 9000, true, true)]);
+        let other = RouteSpec::for_app("a-x", "10.0.0.2");
+        let names = |spec: &RouteSpec| spec.routers().into_iter().map(|r| r.name).collect::<Vec<_>>();
+        let (extra, other) = (names(&extra), names(&other));
+        assert!(extra.iter().all(|name| !other.contains(name)), "{:?} vs {:?}", extra, other);
+    }
+
+    #[test]
+    fn validate_routes_rejects_bad_and_duplicate_routes() {
+        assert!(validate_routes(&[route("ws", "/ws", 9000, true, true)]).is_ok());
+        for bad in [
+            route("", "/ws", 9000, true, true),
+            route("a.b", "/ws", 9000, true, true),
+            route("ide", "/ws", 9000, true, true),
+            route("ws", "ws", 9000, true, true),
+            route("ws", "/", 9000, true, true),
+            route("ws", "/ws", 0, true, true),
+        ] {
+            assert!(validate_routes(std::slice::from_ref(&bad)).is_err(), "{:?}", bad);
+        }
+        let dup = route("ws", "/ws", 9000, true, true);
+        assert!(validate_routes(&[dup.clone(), dup]).unwrap_err().contains("duplicate"));
+    }
+
+    #[test]
+    fn wake_config_only_catches_the_api_prefix() {
+        let config = RouteSpec::for_app("demo", "10.0.0.2").with_ports(3000, 8080).wake_config();
+        let router = &config["routers"]["demo-wake"];
+        assert_eq!(router["rule"], "Path(`/demo`) || PathPrefix(`/demo/`)");
+        assert_eq!(router["priority"], 1);
+        assert_eq!(router["middlewares"], serde_json::json!(["demo-wake-auth", "demo-wake-strip"]));
+        assert_eq!(config["services"]["demo-wake"]["loadBalancer"]["servers"][0]["url"], "http://demo:3000");
+    }
+}
@@ -0,0 +1,175 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::manifest::{self, AppManifest};
+use crate::restarts::APP_LABEL;
+use crate::runtime::{ContainerRuntime, LogSink, RuntimeError, VolumeInfo};
+use crate::AppState;
+
+// ── Persistent data volumes ──
+//
+// `[volumes]` in mcphub.toml maps a volume name to a mount path. Each one is a
+// Docker named volume mcphub_{app}_{name}, created by the manager before the
+// app's containers start and mounted into every replica, so uploads and
+// databases survive redeploys without landing in /apps/{app} (and the IDE).
+// delete_app keeps them unless asked to purge; a kept volume is mounted again
+// by an app of the same name that declares it.
+
+pub const VOLUME_LABEL: &str = "mcphub.volume";
+
+// What delete_app does with the app's volumes
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Retention {
+    #[default]
+    Keep,
+    Purge,
+}
+
+#[derive(Deserialize, Default)]
+pub struct DeleteQuery {
+    #[serde(default)]
+    pub volumes: Retention,
+}
+
+#[derive(Serialize)]
+struct VolumeView {
+    name: String,
+    // Docker volume name
+    volume: String,
+    // Mount path from the manifest; None if no longer declared
+    path: Option<String>,
+    // Whether the Docker volume exists yet (created on the next deploy)
+    created: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<VolumeInfo>,
+}
+
+pub fn volume_name(app_name: &str, name: &str) -> String {
+    format!("mcphub_{}_{}", app_name, name)
+}
+
+// `source:target` binds for the manifest's volumes
+pub fn binds(app_name: &str, manifest: &AppManifest) -> Vec<String> {
+    manifest.volumes
+        .iter()
+        .map(|(name, path)| format!("{}:{}", volume_name(app_name, name), path))
+        .collect()
+}
+
+// Creates any declared volume that doesn't exist yet
+pub async fn ensure(runtime: &dyn ContainerRuntime, app_name: &str, manifest: &AppManifest, log: &LogSink) -> Result<(), String> {
+    for name in manifest.volumes.keys() {
+        let volume = volume_name(app_name, name);
+        match runtime.inspect_volume(&volume).await {
+            Ok(_) => continue,
+            Err(RuntimeError::NotFound(_)) => {}
+            Err(e) => return Err(format!("Failed to inspect volume {}: {}", volume, e)),
+        }
+        let labels = BTreeMap::from([
+            (APP_LABEL.to_string(), app_name.to_string()),
+            (VOLUME_LABEL.to_string(), name.clone()),
+        ]);
+        runtime.create_volume(&volume, &labels).await
+            .map_err(|e| format!("Failed to create volume {}: {}", volume, e))?;
+        let _ = log.send(format!("✓ Created volume {}", volume));
+    }
+    Ok(())
+}
+
+async fn app_volumes(runtime: &dyn ContainerRuntime, app_name: &str) -> Result<Vec<VolumeInfo>, RuntimeError> {
+    runtime.list_volumes(&format!("{}={}", APP_LABEL, app_name)).await
+}
+
+// Removes every volume created for the app (containers must be gone first);
+// returns the removed volume names
+pub async fn purge(runtime: &dyn ContainerRuntime, app_name: &str) -> Result<Vec<String>, String> {
+    let volumes = app_volumes(runtime, app_name).await
+        .map_err(|e| format!("Failed to list volumes: {}", e))?;
+    let mut removed = Vec::new();
+    for volume in volumes {
+        runtime.remove_volume(&volume.name).await
+            .map_err(|e| format!("Failed to remove volume {}: {}", volume.name, e))?;
+        removed.push(volume.name);
+    }
+    Ok(removed)
+}
+
+fn error(status: StatusCode, msg: String) -> Response {
+    (status, Json(serde_json::json!({"error": msg}))).into_response()
+}
+
+// ── Handlers ──
+
+// Declared volumes plus any left over from earlier manifests
+pub async fn list_volumes(
+    State(state): State<Arc<AppState>>,
+    Path(app_name): Path<String>,
+) -> Response {
+    let manifest = manifest::load(&app_name).unwrap_or_default();
+    let existing = match app_volumes(state.runtime.as_ref(), &app_name).await {
+        Ok(v) => v,
+        Err(e) => return error(StatusCode::BAD_GATEWAY, format!("Failed to list volumes: {}", e)),
+    };
+    let mut views: Vec<VolumeView> = manifest.volumes
+        .iter()
+        .map(|(name, path)| {
+            let volume = volume_name(&app_name, name);
+            let details = existing.iter().find(|v| v.name == volume).cloned();
+            VolumeView { name: name.clone(), volume, path: Some(path.clone()), created: details.is_some(), details }
+        })
+        .collect();
+    for details in existing {
+        let name = details.labels.get(VOLUME_LABEL).cloned().unwrap_or_else(|| details.name.clone());
+        if !manifest.volumes.contains_key(&name) {
+            views.push(VolumeView { name, volume: details.name.clone(), path: None, created: true, details: Some(details) });
+        }
+    }
+    Json(serde_json::json!({ "app": app_name, "volumes": views })).into_response()
+}
+
+pub async fn inspect_volume(
+    State(state): State<Arc<AppState>>,
+    Path((app_name, name)): Path<(String, String)>,
+) -> Response {
+    let manifest = manifest::load(&app_name).unwrap_or_default();
+    let volume = volume_name(&app_name, &name);
+    let details = match state.runtime.inspect_volume(&volume).await {
+        Ok(d) => Some(d),
+        Err(RuntimeError::NotFound(_)) => None,
+        Err(e) => return error(StatusCode::BAD_GATEWAY, e.to_string()),
+    };
+    let path = manifest.volumes.get(&name).cloned();
+    if details.is_none() && path.is_none() {
+        return error(StatusCode::NOT_FOUND, "Volume not found".to_string());
+    }
+    Json(VolumeView { name, volume, path, created: details.is_some(), details }).into_response()
+}
+
+pub async fn volume_size(
+    State(state): State<Arc<AppState>>,
+    Path((app_name, name)): Path<(String, String)>,
+) -> Response {
+    let volume = volume_name(&app_name, &name);
+    let usage = match state.runtime.volume_usage().await {
+        Ok(u) => u,
+        Err(e) => return error(StatusCode::BAD_GATEWAY, format!("Failed to read volume usage: {}", e)),
+    };
+    match usage.get(&volume) {
+        Some(usage) => Json(serde_json::json!({
+            "name": name,
+            "volume": volume,
+            "size_bytes": usage.size_bytes,
+            "ref_count": usage.ref_count,
+        }))
+        .into_response(),
+        None => error(StatusCode::NOT_FOUND, "Volume not found".to_string()),
+    }
+}
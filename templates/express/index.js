@@ -0,0 +1,16 @@
+const express = require("express");
+
+const app = express();
+app.use(express.json());
+
+app.get("/", (req, res) => {
+  res.json({ message: "{{message}}" });
+});
+
+app.get("/health", (req, res) => {
+  res.json({ status: "ok" });
+});
+
+app.listen(80, "0.0.0.0", () => {
+  console.log("Listening on :80");
+});
@@ -0,0 +1,13 @@
+from flask import Flask, jsonify
+
+app = Flask(__name__)
+
+
+@app.get("/")
+def read_root():
+    return jsonify(message="{{message}}")
+
+
+@app.get("/health")
+def health():
+    return jsonify(status="ok")